@@ -0,0 +1,2 @@
+pub mod services;
+pub mod queries;
\ No newline at end of file
@@ -0,0 +1,139 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use crate::execution::domain::entities::{ExecutionLimits, OutputCapture};
+
+/// Where captured output of execution has to be stored
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputTarget {
+    /// Last-run metadata of executed record
+    LastRun,
+    /// Separate record with specific key, created or replaced
+    Record(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveOutput {
+    pub target: OutputTarget,
+    pub capture: OutputCapture,
+}
+
+pub struct ExecuteRecordQuery {
+    pub key: String,
+    pub cwd: PathBuf,
+    pub save_output: Option<SaveOutput>,
+    /// Limits overriding defaults stored in the record
+    pub limits: ExecutionLimits,
+}
+
+impl ExecuteRecordQuery {
+    pub fn new(key: String, cwd: PathBuf, save_output: Option<SaveOutput>) -> ExecuteRecordQuery {
+        ExecuteRecordQuery { key, cwd, save_output, limits: ExecutionLimits::default() }
+    }
+
+    pub fn with_limits(self, limits: ExecutionLimits) -> ExecuteRecordQuery {
+        ExecuteRecordQuery { limits, ..self }
+    }
+}
+
+pub struct RunWorkflowQuery {
+    pub key: String,
+    pub cwd: PathBuf,
+    /// Run remaining steps even after one of them failed
+    pub continue_on_error: bool,
+    /// Limits overriding defaults stored in every step record
+    pub limits: ExecutionLimits,
+}
+
+impl RunWorkflowQuery {
+    pub fn new(key: String, cwd: PathBuf, continue_on_error: bool, limits: ExecutionLimits) -> RunWorkflowQuery {
+        RunWorkflowQuery { key, cwd, continue_on_error, limits }
+    }
+}
+
+pub struct RunParallelQuery {
+    pub keys: Vec<String>,
+    /// Execute all records with this tag in addition to `keys`
+    pub tag: Option<String>,
+    pub cwd: PathBuf,
+    /// Maximum number of records executed at once, all of them when not set
+    pub jobs: Option<usize>,
+    /// Limits overriding defaults stored in every record
+    pub limits: ExecutionLimits,
+}
+
+impl RunParallelQuery {
+    pub fn new(
+        keys: Vec<String>,
+        tag: Option<String>,
+        cwd: PathBuf,
+        jobs: Option<usize>,
+        limits: ExecutionLimits,
+    ) -> RunParallelQuery {
+        RunParallelQuery { keys, tag, cwd, jobs, limits }
+    }
+}
+
+pub struct ListHistoryQuery {
+    pub key: Option<String>,
+    pub failed_only: bool,
+}
+
+impl ListHistoryQuery {
+    pub fn new(key: &Option<String>, failed_only: bool) -> ListHistoryQuery {
+        ListHistoryQuery { key: key.to_owned(), failed_only }
+    }
+}
+
+pub struct RerunHistoryQuery {
+    /// 1-based number of entry as shown by history listing
+    pub number: usize,
+}
+
+impl RerunHistoryQuery {
+    pub fn new(number: usize) -> RerunHistoryQuery {
+        RerunHistoryQuery { number }
+    }
+}
+
+pub struct StartJobQuery {
+    pub key: String,
+    pub cwd: PathBuf,
+}
+
+impl StartJobQuery {
+    pub fn new(key: String, cwd: PathBuf) -> StartJobQuery {
+        StartJobQuery { key, cwd }
+    }
+}
+
+pub struct ListJobsQuery {
+    pub running_only: bool,
+}
+
+impl ListJobsQuery {
+    pub fn new(running_only: bool) -> ListJobsQuery {
+        ListJobsQuery { running_only }
+    }
+}
+
+pub struct GetJobLogQuery {
+    pub id: u32,
+}
+
+impl GetJobLogQuery {
+    pub fn new(id: u32) -> GetJobLogQuery {
+        GetJobLogQuery { id }
+    }
+}
+
+pub struct KillJobQuery {
+    pub id: u32,
+}
+
+impl KillJobQuery {
+    pub fn new(id: u32) -> KillJobQuery {
+        KillJobQuery { id }
+    }
+}
@@ -0,0 +1,801 @@
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use super::queries::{
+    ExecuteRecordQuery, GetJobLogQuery, KillJobQuery, ListHistoryQuery, ListJobsQuery, OutputTarget,
+    RerunHistoryQuery, RunParallelQuery, RunWorkflowQuery, StartJobQuery,
+};
+use crate::execution::domain::{
+    entities::{CommandSpec, ExecutionLimits, HistoryEntry, Job, StepOutcome, StepResult},
+    repositories::{HistoryRepository, JobRepository},
+    runners::{BackgroundRunner, CommandRunner},
+};
+use crate::record::domain::{entities::Record, repositories::RecordRepository};
+use crate::shared::domain::error::Error;
+
+/// Run command once, describing the run as history entry.
+/// Returns created history entry and captured output, if any
+fn run_once(runner: &dyn CommandRunner, key: &str, spec: &CommandSpec) -> Result<(HistoryEntry, Option<String>), Error> {
+    let timestamp = Utc::now().timestamp();
+    let result = runner.run(spec)?;
+    let entry = HistoryEntry {
+        timestamp,
+        key: key.to_string(),
+        command: spec.command.to_string(),
+        interpreter: spec.interpreter.clone(),
+        cwd: spec.cwd.to_string_lossy().to_string(),
+        env: spec.env.clone(),
+        exit_code: result.exit_code,
+        duration_ms: result.duration.as_millis() as u64,
+        timed_out: result.timed_out,
+    };
+    Ok((entry, result.output))
+}
+
+/// Run command until it succeeds or retries are exhausted, passing every attempt to `record_attempt`.
+/// The last attempt determines the outcome
+fn run_with_retries(
+    runner: &dyn CommandRunner,
+    key: &str,
+    spec: &CommandSpec,
+    limits: &ExecutionLimits,
+    record_attempt: &mut dyn FnMut(&HistoryEntry) -> Result<(), Error>,
+) -> Result<(HistoryEntry, Option<String>), Error> {
+    let mut attempts_left = limits.retries.unwrap_or(0);
+    let (mut entry, mut output) = run_once(runner, key, spec)?;
+    record_attempt(&entry)?;
+    while !entry.succeeded() && attempts_left > 0 {
+        attempts_left -= 1;
+        thread::sleep(limits.retry_delay.unwrap_or_default());
+        let (next_entry, next_output) = run_once(runner, key, spec)?;
+        record_attempt(&next_entry)?;
+        entry = next_entry;
+        output = next_output;
+    }
+    Ok((entry, output))
+}
+
+/// Build command for a record, applying its directory, environment and default limits
+fn prepare_command(record: &Record, cwd: &Path, limits: &ExecutionLimits) -> Result<(CommandSpec, ExecutionLimits), Error> {
+    if record.metadata.is_workflow() {
+        return Err(Error::Invalid(format!("Record \"{}\" is a workflow and can not be executed as a command", record.key)));
+    }
+    // Relative record directory is resolved against the current one
+    let cwd = match &record.metadata.cwd {
+        Some(record_cwd) => cwd.join(record_cwd),
+        None => cwd.to_path_buf(),
+    };
+    let defaults = ExecutionLimits {
+        timeout: record.metadata.timeout_ms.map(Duration::from_millis),
+        retries: record.metadata.retries,
+        retry_delay: record.metadata.retry_delay_ms.map(Duration::from_millis),
+    };
+    let limits = limits.or(&defaults);
+    let mut spec = CommandSpec::new(&record.value, &cwd);
+    spec.interpreter = record.metadata.interpreter.clone();
+    spec.env = record.metadata.env.clone();
+    spec.timeout = limits.timeout;
+    Ok((spec, limits))
+}
+
+pub struct ExecuteRecordService<'a> {
+    record_repository: &'a dyn RecordRepository,
+    history_repository: &'a dyn HistoryRepository,
+    runner: &'a dyn CommandRunner,
+}
+
+impl<'a> ExecuteRecordService<'a> {
+    pub fn new(
+        record_repository: &'a dyn RecordRepository,
+        history_repository: &'a dyn HistoryRepository,
+        runner: &'a dyn CommandRunner,
+    ) -> ExecuteRecordService<'a> {
+        ExecuteRecordService { record_repository, history_repository, runner }
+    }
+
+    pub fn run(&self, query: &ExecuteRecordQuery) -> Result<HistoryEntry, Error> {
+        let record = self.record_repository.get(query.key.to_string())?;
+        let (mut spec, limits) = prepare_command(&record, &query.cwd, &query.limits)?;
+        spec.capture = query.save_output.as_ref().map(|save| save.capture.clone());
+
+        let history_repository = self.history_repository;
+        let (entry, output) = run_with_retries(self.runner, &record.key, &spec, &limits, &mut |entry| {
+            history_repository.append(entry.clone())
+        })?;
+        if let (Some(save), Some(output)) = (&query.save_output, output) {
+            self.store_output(record, &save.target, &output)?;
+        }
+        Ok(entry)
+    }
+
+    fn store_output(&self, mut record: Record, target: &OutputTarget, output: &str) -> Result<(), Error> {
+        // Trailing newlines are dropped the same way shell command substitution does
+        let output = output.trim_end_matches(&['\n', '\r'][..]).to_string();
+        match target {
+            OutputTarget::LastRun => {
+                record.metadata.last_output = Some(output);
+                self.record_repository.update(record)
+            }
+            OutputTarget::Record(key) if output.is_empty() => {
+                Err(Error::Exec(format!("Command produced no output to save into \"{}\"", key)))
+            }
+            OutputTarget::Record(key) => match self.record_repository.get(key.to_string()) {
+                Ok(mut existing) => {
+                    existing.value = output;
+                    self.record_repository.update(existing)
+                }
+                Err(_) => self.record_repository.add(Record::new(key, &output)),
+            },
+        }
+    }
+}
+
+pub struct RunWorkflowService<'a> {
+    record_repository: &'a dyn RecordRepository,
+    history_repository: &'a dyn HistoryRepository,
+    runner: &'a dyn CommandRunner,
+}
+
+impl<'a> RunWorkflowService<'a> {
+    pub fn new(
+        record_repository: &'a dyn RecordRepository,
+        history_repository: &'a dyn HistoryRepository,
+        runner: &'a dyn CommandRunner,
+    ) -> RunWorkflowService<'a> {
+        RunWorkflowService { record_repository, history_repository, runner }
+    }
+
+    /// Run workflow steps one by one, every step is executed as a separate record
+    pub fn run(&self, query: &RunWorkflowQuery) -> Result<Vec<StepResult>, Error> {
+        let workflow = self.record_repository.get(query.key.to_string())?;
+        if !workflow.metadata.is_workflow() {
+            return Err(Error::Invalid(format!("Record \"{}\" is not a workflow", workflow.key)));
+        }
+        let mut steps = Vec::new();
+        self.collect_steps(&workflow.key, &mut Vec::new(), &mut steps)?;
+
+        let executor = ExecuteRecordService::new(self.record_repository, self.history_repository, self.runner);
+        let mut failed = false;
+        let mut results = Vec::new();
+        for key in steps {
+            let outcome = if failed && !query.continue_on_error {
+                StepOutcome::Skipped
+            } else {
+                let step_query = ExecuteRecordQuery::new(key.to_string(), query.cwd.clone(), None)
+                    .with_limits(query.limits.clone());
+                match executor.run(&step_query) {
+                    Ok(entry) => StepOutcome::Finished(entry),
+                    Err(e) => StepOutcome::Failed(e.to_string()),
+                }
+            };
+            let result = StepResult { key, outcome };
+            failed = failed || !result.succeeded();
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Expand nested workflows into plain list of record keys
+    fn collect_steps(&self, key: &str, parents: &mut Vec<String>, steps: &mut Vec<String>) -> Result<(), Error> {
+        let record = match self.record_repository.get(key.to_string()) {
+            Ok(record) if record.metadata.is_workflow() => record,
+            // Missing records are reported when their step is reached
+            _ => {
+                steps.push(key.to_string());
+                return Ok(());
+            }
+        };
+        if parents.iter().any(|parent| parent == key) {
+            return Err(Error::Invalid(format!("Workflow \"{}\" includes itself", key)));
+        }
+        parents.push(key.to_string());
+        for step in &record.metadata.steps {
+            self.collect_steps(step, parents, steps)?;
+        }
+        parents.pop();
+        Ok(())
+    }
+}
+
+pub struct RunParallelService<'a> {
+    record_repository: &'a dyn RecordRepository,
+    history_repository: &'a dyn HistoryRepository,
+    runner: &'a dyn CommandRunner,
+}
+
+impl<'a> RunParallelService<'a> {
+    pub fn new(
+        record_repository: &'a dyn RecordRepository,
+        history_repository: &'a dyn HistoryRepository,
+        runner: &'a dyn CommandRunner,
+    ) -> RunParallelService<'a> {
+        RunParallelService { record_repository, history_repository, runner }
+    }
+
+    /// Run records concurrently, output lines of every record are prefixed with its key
+    pub fn run(&self, query: &RunParallelQuery) -> Result<Vec<StepResult>, Error> {
+        let keys = self.resolve_keys(query)?;
+        let width = keys.iter().map(|key| key.len()).max().unwrap_or(0);
+        // Commands are prepared upfront, repositories can not be shared between threads
+        let commands: Vec<_> = keys
+            .into_iter()
+            .map(|key| {
+                let command = self
+                    .record_repository
+                    .get(key.to_string())
+                    .and_then(|record| prepare_command(&record, &query.cwd, &query.limits))
+                    .map(|(mut spec, limits)| {
+                        spec.output_prefix = Some(format!("[{:width$}] ", key, width = width));
+                        (spec, limits)
+                    });
+                (key, command)
+            })
+            .collect();
+
+        let runner = self.runner;
+        let next = AtomicUsize::new(0);
+        let finished = Mutex::new(Vec::new());
+        let jobs = query.jobs.unwrap_or(commands.len()).clamp(1, commands.len());
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let (key, command) = match commands.get(index) {
+                        Some(command) => command,
+                        None => break,
+                    };
+                    let mut attempts = Vec::new();
+                    let outcome = match command {
+                        Ok((spec, limits)) => {
+                            let mut record_attempt = |entry: &HistoryEntry| {
+                                attempts.push(entry.clone());
+                                Ok(())
+                            };
+                            match run_with_retries(runner, key, spec, limits, &mut record_attempt) {
+                                Ok((entry, _)) => StepOutcome::Finished(entry),
+                                Err(e) => StepOutcome::Failed(e.to_string()),
+                            }
+                        }
+                        Err(e) => StepOutcome::Failed(e.to_string()),
+                    };
+                    let result = StepResult { key: key.to_string(), outcome };
+                    finished.lock().unwrap().push((index, attempts, result));
+                });
+            }
+        });
+
+        let mut finished = finished.into_inner().unwrap();
+        finished.sort_by_key(|(index, _, _)| *index);
+        let mut results = Vec::new();
+        for (_, attempts, result) in finished {
+            for entry in attempts {
+                self.history_repository.append(entry)?;
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Explicitly given keys followed by keys of tagged records, without duplicates
+    fn resolve_keys(&self, query: &RunParallelQuery) -> Result<Vec<String>, Error> {
+        let mut keys: Vec<String> = Vec::new();
+        for key in &query.keys {
+            if !keys.contains(key) {
+                keys.push(key.to_string());
+            }
+        }
+        if let Some(tag) = &query.tag {
+            let mut tagged: Vec<String> = self
+                .record_repository
+                .all()?
+                .into_iter()
+                .filter(|record| record.metadata.tags.contains(tag))
+                .map(|record| record.key)
+                .collect();
+            if tagged.is_empty() {
+                return Err(Error::NotFound(format!("There are no records with tag \"{}\"", tag)));
+            }
+            tagged.sort();
+            keys.extend(tagged.into_iter().filter(|key| !query.keys.contains(key)));
+        }
+        if keys.is_empty() {
+            return Err(Error::NotFound("No records to execute".to_string()));
+        }
+        Ok(keys)
+    }
+}
+
+pub struct ListHistoryService<'a> {
+    history_repository: &'a dyn HistoryRepository,
+}
+
+impl<'a> ListHistoryService<'a> {
+    pub fn new(history_repository: &'a dyn HistoryRepository) -> ListHistoryService<'a> {
+        ListHistoryService { history_repository }
+    }
+
+    /// Get matching entries together with their 1-based numbers in the whole history
+    pub fn run(&self, query: &ListHistoryQuery) -> Result<Vec<(usize, HistoryEntry)>, Error> {
+        let entries = self.history_repository.all()?;
+        Ok(entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| (i + 1, entry))
+            .filter(|(_, entry)| match &query.key {
+                Some(key) => &entry.key == key,
+                None => true,
+            })
+            .filter(|(_, entry)| !query.failed_only || !entry.succeeded())
+            .collect())
+    }
+}
+
+pub struct RerunHistoryService<'a> {
+    history_repository: &'a dyn HistoryRepository,
+    runner: &'a dyn CommandRunner,
+}
+
+impl<'a> RerunHistoryService<'a> {
+    pub fn new(
+        history_repository: &'a dyn HistoryRepository,
+        runner: &'a dyn CommandRunner,
+    ) -> RerunHistoryService<'a> {
+        RerunHistoryService { history_repository, runner }
+    }
+
+    pub fn run(&self, query: &RerunHistoryQuery) -> Result<HistoryEntry, Error> {
+        let entries = self.history_repository.all()?;
+        let entry = query
+            .number
+            .checked_sub(1)
+            .and_then(|i| entries.get(i))
+            .ok_or_else(|| Error::NotFound(format!("History entry {} does not exist", query.number)))?;
+        let mut spec = CommandSpec::new(&entry.command, Path::new(&entry.cwd));
+        spec.interpreter = entry.interpreter.clone();
+        spec.env = entry.env.clone();
+        let (entry, _) = run_once(self.runner, &entry.key, &spec)?;
+        self.history_repository.append(entry.clone())?;
+        Ok(entry)
+    }
+}
+
+fn find_job(job_repository: &dyn JobRepository, id: u32) -> Result<Job, Error> {
+    job_repository
+        .all()?
+        .into_iter()
+        .find(|job| job.id == id)
+        .ok_or_else(|| Error::NotFound(format!("Job {} does not exist", id)))
+}
+
+pub struct StartJobService<'a> {
+    record_repository: &'a dyn RecordRepository,
+    job_repository: &'a dyn JobRepository,
+    runner: &'a dyn BackgroundRunner,
+}
+
+impl<'a> StartJobService<'a> {
+    pub fn new(
+        record_repository: &'a dyn RecordRepository,
+        job_repository: &'a dyn JobRepository,
+        runner: &'a dyn BackgroundRunner,
+    ) -> StartJobService<'a> {
+        StartJobService { record_repository, job_repository, runner }
+    }
+
+    /// Start record in background. Timeouts and retries are not applied to jobs
+    pub fn run(&self, query: &StartJobQuery) -> Result<Job, Error> {
+        let record = self.record_repository.get(query.key.to_string())?;
+        let (spec, _) = prepare_command(&record, &query.cwd, &ExecutionLimits::default())?;
+        let id = self.job_repository.all()?.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        let log = self.job_repository.log_location(id)?;
+        let timestamp = Utc::now().timestamp();
+        let process = self.runner.spawn(&spec, &log)?;
+        let job = Job {
+            id,
+            process,
+            timestamp,
+            key: record.key,
+            command: spec.command,
+            cwd: spec.cwd.to_string_lossy().to_string(),
+            log: log.to_string_lossy().to_string(),
+        };
+        self.job_repository.add(job.clone())?;
+        Ok(job)
+    }
+}
+
+pub struct ListJobsService<'a> {
+    job_repository: &'a dyn JobRepository,
+    runner: &'a dyn BackgroundRunner,
+}
+
+impl<'a> ListJobsService<'a> {
+    pub fn new(job_repository: &'a dyn JobRepository, runner: &'a dyn BackgroundRunner) -> ListJobsService<'a> {
+        ListJobsService { job_repository, runner }
+    }
+
+    /// Get jobs together with flag telling whether they are still running
+    pub fn run(&self, query: &ListJobsQuery) -> Result<Vec<(Job, bool)>, Error> {
+        Ok(self
+            .job_repository
+            .all()?
+            .into_iter()
+            .map(|job| {
+                let running = self.runner.is_running(&job.process);
+                (job, running)
+            })
+            .filter(|(_, running)| *running || !query.running_only)
+            .collect())
+    }
+}
+
+pub struct GetJobLogService<'a> {
+    job_repository: &'a dyn JobRepository,
+}
+
+impl<'a> GetJobLogService<'a> {
+    pub fn new(job_repository: &'a dyn JobRepository) -> GetJobLogService<'a> {
+        GetJobLogService { job_repository }
+    }
+
+    pub fn run(&self, query: &GetJobLogQuery) -> Result<String, Error> {
+        let job = find_job(self.job_repository, query.id)?;
+        self.job_repository.read_log(&job)
+    }
+}
+
+pub struct KillJobService<'a> {
+    job_repository: &'a dyn JobRepository,
+    runner: &'a dyn BackgroundRunner,
+}
+
+impl<'a> KillJobService<'a> {
+    pub fn new(job_repository: &'a dyn JobRepository, runner: &'a dyn BackgroundRunner) -> KillJobService<'a> {
+        KillJobService { job_repository, runner }
+    }
+
+    pub fn run(&self, query: &KillJobQuery) -> Result<Job, Error> {
+        let job = find_job(self.job_repository, query.id)?;
+        if !self.runner.is_running(&job.process) {
+            return Err(Error::Invalid(format!("Job {} is not running", job.id)));
+        }
+        self.runner.terminate(&job.process)?;
+        Ok(job)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use crate::execution::application::queries::SaveOutput;
+    use crate::execution::domain::entities::{ExecutionResult, OutputCapture, ProcessId};
+    use crate::execution::infrastructure::repositories::{InMemoryHistoryRepository, InMemoryJobRepository};
+    use crate::record::domain::entities::Record;
+    use crate::record::infrastructure::repositories::InMemoryRecordRepository;
+
+    use super::*;
+
+    /// Runner that remembers commands and exits with predefined code
+    struct FakeRunner {
+        /// Exit codes of consecutive runs, the last one is repeated
+        exit_codes: Vec<i32>,
+        specs: Mutex<Vec<CommandSpec>>,
+    }
+
+    impl FakeRunner {
+        fn new(exit_code: i32) -> FakeRunner {
+            FakeRunner::with_codes(vec![exit_code])
+        }
+
+        fn with_codes(exit_codes: Vec<i32>) -> FakeRunner {
+            FakeRunner { exit_codes, specs: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(&self, spec: &CommandSpec) -> Result<ExecutionResult, Error> {
+            let mut specs = self.specs.lock().unwrap();
+            specs.push(spec.clone());
+            let run = specs.len().min(self.exit_codes.len());
+            Ok(ExecutionResult {
+                exit_code: Some(self.exit_codes[run - 1]),
+                duration: Duration::from_millis(5),
+                timed_out: false,
+                output: spec.capture.as_ref().map(|_| format!("output of {}\n", spec.command)),
+            })
+        }
+    }
+
+    /// Background runner pretending that processes run until they are terminated
+    struct FakeBackgroundRunner {
+        running: Mutex<Vec<u32>>,
+        specs: Mutex<Vec<CommandSpec>>,
+    }
+
+    impl FakeBackgroundRunner {
+        fn new() -> FakeBackgroundRunner {
+            FakeBackgroundRunner { running: Mutex::new(Vec::new()), specs: Mutex::new(Vec::new()) }
+        }
+    }
+
+    impl BackgroundRunner for FakeBackgroundRunner {
+        fn spawn(&self, spec: &CommandSpec, _log: &Path) -> Result<ProcessId, Error> {
+            let mut specs = self.specs.lock().unwrap();
+            specs.push(spec.clone());
+            let pid = 1000 + specs.len() as u32;
+            self.running.lock().unwrap().push(pid);
+            Ok(ProcessId { pid, start_time: Some(0) })
+        }
+
+        fn is_running(&self, process: &ProcessId) -> bool {
+            self.running.lock().unwrap().contains(&process.pid)
+        }
+
+        fn terminate(&self, process: &ProcessId) -> Result<(), Error> {
+            self.running.lock().unwrap().retain(|running| *running != process.pid);
+            Ok(())
+        }
+    }
+
+    fn records() -> InMemoryRecordRepository {
+        let repository = InMemoryRecordRepository::new();
+        repository.add(Record::new(&"hi".to_string(), &"echo hi".to_string())).unwrap();
+        repository.add(Record::new(&"fail".to_string(), &"false".to_string())).unwrap();
+        repository
+    }
+
+    #[test]
+    fn execute_appends_history_entry() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        let service = ExecuteRecordService::new(&records, &history, &runner);
+        let entry = service.run(&ExecuteRecordQuery::new("hi".to_string(), PathBuf::from("/tmp"), None)).unwrap();
+
+        assert_eq!(entry.command, "echo hi");
+        assert_eq!(entry.cwd, "/tmp");
+        assert_eq!(entry.exit_code, Some(0));
+        assert_eq!(history.all().unwrap(), vec![entry]);
+    }
+
+    fn save_output(target: OutputTarget) -> Option<SaveOutput> {
+        let capture = OutputCapture { stderr: false, tee: false, limit: 1024 };
+        Some(SaveOutput { target, capture })
+    }
+
+    #[test]
+    fn execute_saves_output_into_last_run_metadata() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        let service = ExecuteRecordService::new(&records, &history, &runner);
+        let query = ExecuteRecordQuery::new("hi".to_string(), PathBuf::from("/"), save_output(OutputTarget::LastRun));
+        service.run(&query).unwrap();
+
+        let record = records.get("hi".to_string()).unwrap();
+        assert_eq!(record.value, "echo hi");
+        assert_eq!(record.metadata.last_output, Some("output of echo hi".to_string()));
+    }
+
+    #[test]
+    fn execute_saves_output_into_other_record() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        let service = ExecuteRecordService::new(&records, &history, &runner);
+        for _ in 0..2 {
+            let target = OutputTarget::Record("token".to_string());
+            let query = ExecuteRecordQuery::new("hi".to_string(), PathBuf::from("/"), save_output(target));
+            service.run(&query).unwrap();
+        }
+
+        assert_eq!(records.get("token".to_string()).unwrap().value, "output of echo hi");
+        assert_eq!(records.get("hi".to_string()).unwrap().metadata.last_output, None);
+    }
+
+    #[test]
+    fn execute_applies_record_directory_and_environment() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        let mut record = Record::new(&"env".to_string(), &"printenv".to_string());
+        record.metadata.cwd = Some("project".to_string());
+        record.metadata.env.insert("KUBECONFIG".to_string(), "/tmp/kube".to_string());
+        records.add(record).unwrap();
+
+        let service = ExecuteRecordService::new(&records, &history, &runner);
+        let entry = service.run(&ExecuteRecordQuery::new("env".to_string(), PathBuf::from("/home"), None)).unwrap();
+        let spec = runner.specs.lock().unwrap()[0].clone();
+        assert_eq!(spec.cwd, PathBuf::from("/home/project"));
+        assert_eq!(spec.env.get("KUBECONFIG"), Some(&"/tmp/kube".to_string()));
+        assert_eq!(entry.env, spec.env);
+
+        RerunHistoryService::new(&history, &runner).run(&RerunHistoryQuery::new(1)).unwrap();
+        assert_eq!(runner.specs.lock().unwrap()[1], spec);
+    }
+
+    #[test]
+    fn execute_retries_until_success() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::with_codes(vec![1, 1, 0, 1]));
+        let limits = ExecutionLimits { timeout: None, retries: Some(5), retry_delay: Some(Duration::from_millis(1)) };
+        let query = ExecuteRecordQuery::new("fail".to_string(), PathBuf::from("/"), None).with_limits(limits);
+        let entry = ExecuteRecordService::new(&records, &history, &runner).run(&query).unwrap();
+
+        assert!(entry.succeeded());
+        assert_eq!(history.all().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn execute_uses_record_defaults_and_reports_last_attempt() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::with_codes(vec![1, 2, 3, 0]));
+        let mut record = records.get("fail".to_string()).unwrap();
+        record.metadata.retries = Some(2);
+        record.metadata.timeout_ms = Some(1500);
+        records.update(record).unwrap();
+
+        let query = ExecuteRecordQuery::new("fail".to_string(), PathBuf::from("/"), None);
+        let entry = ExecuteRecordService::new(&records, &history, &runner).run(&query).unwrap();
+        assert_eq!(entry.exit_code, Some(3));
+        assert_eq!(runner.specs.lock().unwrap()[0].timeout, Some(Duration::from_millis(1500)));
+    }
+
+    fn add_workflow(records: &InMemoryRecordRepository, key: &str, steps: &[&str]) {
+        let mut record = Record::new(&key.to_string(), &steps.join(" -> "));
+        record.metadata.steps = steps.iter().map(|step| step.to_string()).collect();
+        records.add(record).unwrap();
+    }
+
+    #[test]
+    fn workflow_stops_on_failure_by_default() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::with_codes(vec![0, 1]));
+        add_workflow(&records, "inner", &["hi", "fail"]);
+        add_workflow(&records, "flow", &["inner", "hi"]);
+
+        let service = RunWorkflowService::new(&records, &history, &runner);
+        let query = RunWorkflowQuery::new("flow".to_string(), PathBuf::from("/"), false, ExecutionLimits::default());
+        let results = service.run(&query).unwrap();
+
+        let keys: Vec<&str> = results.iter().map(|result| result.key.as_str()).collect();
+        assert_eq!(keys, vec!["hi", "fail", "hi"]);
+        assert!(results[0].succeeded());
+        assert!(!results[1].succeeded());
+        assert_eq!(results[2].outcome, StepOutcome::Skipped);
+        assert_eq!(history.all().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn workflow_continues_on_error_when_requested() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::with_codes(vec![1, 0]));
+        add_workflow(&records, "flow", &["fail", "missing", "hi"]);
+
+        let service = RunWorkflowService::new(&records, &history, &runner);
+        let query = RunWorkflowQuery::new("flow".to_string(), PathBuf::from("/"), true, ExecutionLimits::default());
+        let results = service.run(&query).unwrap();
+
+        assert!(matches!(results[1].outcome, StepOutcome::Failed(_)));
+        assert!(results[2].succeeded());
+    }
+
+    #[test]
+    fn workflow_fails_for_cycles() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        add_workflow(&records, "a", &["hi", "b"]);
+        add_workflow(&records, "b", &["a"]);
+
+        let service = RunWorkflowService::new(&records, &history, &runner);
+        let query = RunWorkflowQuery::new("a".to_string(), PathBuf::from("/"), false, ExecutionLimits::default());
+        assert!(service.run(&query).is_err());
+        assert!(runner.specs.lock().unwrap().is_empty());
+    }
+
+    fn tagged(records: &InMemoryRecordRepository, key: &str, tag: &str) {
+        let mut record = Record::new(&key.to_string(), &format!("run {}", key));
+        record.metadata.tags = vec![tag.to_string()];
+        records.add(record).unwrap();
+    }
+
+    #[test]
+    fn parallel_runs_given_and_tagged_records() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        tagged(&records, "web", "dev");
+        tagged(&records, "api", "dev");
+
+        let service = RunParallelService::new(&records, &history, &runner);
+        let keys = vec!["hi".to_string(), "web".to_string()];
+        let query = RunParallelQuery::new(keys, Some("dev".to_string()), PathBuf::from("/"), Some(2), ExecutionLimits::default());
+        let results = service.run(&query).unwrap();
+
+        let keys: Vec<&str> = results.iter().map(|result| result.key.as_str()).collect();
+        assert_eq!(keys, vec!["hi", "web", "api"]);
+        assert!(results.iter().all(|result| result.succeeded()));
+        assert_eq!(history.all().unwrap().len(), 3);
+        let prefixes: Vec<Option<String>> = runner.specs.lock().unwrap().iter().map(|spec| spec.output_prefix.clone()).collect();
+        assert!(prefixes.contains(&Some("[api] ".to_string())));
+        assert!(prefixes.contains(&Some("[hi ] ".to_string())));
+    }
+
+    #[test]
+    fn parallel_reports_failures_and_unknown_records() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(1));
+        let service = RunParallelService::new(&records, &history, &runner);
+        let keys = vec!["fail".to_string(), "missing".to_string()];
+        let query = RunParallelQuery::new(keys, None, PathBuf::from("/"), None, ExecutionLimits::default());
+        let results = service.run(&query).unwrap();
+
+        assert!(!results[0].succeeded());
+        assert!(matches!(results[1].outcome, StepOutcome::Failed(_)));
+
+        let unknown_tag = RunParallelQuery::new(vec![], Some("none".to_string()), PathBuf::from("/"), None, ExecutionLimits::default());
+        assert!(service.run(&unknown_tag).is_err());
+    }
+
+    #[test]
+    fn list_filters_by_key_and_failure_keeping_numbers() {
+        let (records, history) = (records(), InMemoryHistoryRepository::new());
+        let (ok_runner, bad_runner) = (FakeRunner::new(0), FakeRunner::new(1));
+        let cwd = PathBuf::from("/");
+        ExecuteRecordService::new(&records, &history, &ok_runner)
+            .run(&ExecuteRecordQuery::new("hi".to_string(), cwd.clone(), None))
+            .unwrap();
+        ExecuteRecordService::new(&records, &history, &bad_runner)
+            .run(&ExecuteRecordQuery::new("fail".to_string(), cwd, None))
+            .unwrap();
+
+        let service = ListHistoryService::new(&history);
+        let failed = service.run(&ListHistoryQuery::new(&None, true)).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].0, 2);
+        let by_key = service.run(&ListHistoryQuery::new(&Some("hi".to_string()), false)).unwrap();
+        assert_eq!(by_key.len(), 1);
+        assert_eq!(by_key[0].0, 1);
+    }
+
+    #[test]
+    fn rerun_runs_recorded_command() {
+        let (records, history, runner) = (records(), InMemoryHistoryRepository::new(), FakeRunner::new(0));
+        ExecuteRecordService::new(&records, &history, &runner)
+            .run(&ExecuteRecordQuery::new("hi".to_string(), PathBuf::from("/"), None))
+            .unwrap();
+        records.remove("hi".to_string()).unwrap();
+
+        let service = RerunHistoryService::new(&history, &runner);
+        let entry = service.run(&RerunHistoryQuery::new(1)).unwrap();
+        assert_eq!(entry.command, "echo hi");
+        assert_eq!(history.all().unwrap().len(), 2);
+        assert!(service.run(&RerunHistoryQuery::new(0)).is_err());
+        assert!(service.run(&RerunHistoryQuery::new(10)).is_err());
+    }
+
+    #[test]
+    fn start_job_registers_job_with_log() {
+        let (records, jobs, runner) = (records(), InMemoryJobRepository::new(Path::new("/jobs")), FakeBackgroundRunner::new());
+        let service = StartJobService::new(&records, &jobs, &runner);
+        let first = service.run(&StartJobQuery::new("hi".to_string(), PathBuf::from("/tmp"))).unwrap();
+        let second = service.run(&StartJobQuery::new("fail".to_string(), PathBuf::from("/tmp"))).unwrap();
+
+        assert_eq!((first.id, first.process.pid, first.command.as_str()), (1, 1001, "echo hi"));
+        assert_eq!(first.log, "/jobs/1.log");
+        assert_eq!(second.id, 2);
+        assert_eq!(jobs.all().unwrap(), vec![first, second]);
+        assert!(service.run(&StartJobQuery::new("missing".to_string(), PathBuf::from("/"))).is_err());
+    }
+
+    #[test]
+    fn kill_job_stops_only_running_jobs() {
+        let (records, jobs, runner) = (records(), InMemoryJobRepository::new(Path::new("/jobs")), FakeBackgroundRunner::new());
+        StartJobService::new(&records, &jobs, &runner)
+            .run(&StartJobQuery::new("hi".to_string(), PathBuf::from("/")))
+            .unwrap();
+        let list = ListJobsService::new(&jobs, &runner);
+        assert_eq!(list.run(&ListJobsQuery::new(true)).unwrap().len(), 1);
+
+        let service = KillJobService::new(&jobs, &runner);
+        assert_eq!(service.run(&KillJobQuery::new(1)).unwrap().key, "hi");
+        assert!(service.run(&KillJobQuery::new(1)).is_err());
+        assert!(service.run(&KillJobQuery::new(2)).is_err());
+        assert!(list.run(&ListJobsQuery::new(true)).unwrap().is_empty());
+        assert!(!list.run(&ListJobsQuery::new(false)).unwrap()[0].1);
+        assert_eq!(GetJobLogService::new(&jobs).run(&GetJobLogQuery::new(1)).unwrap(), "log of hi");
+    }
+}
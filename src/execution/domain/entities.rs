@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::record::domain::scripts::has_shebang;
+
+/// How output of a command has to be captured
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputCapture {
+    /// Capture stderr together with stdout
+    pub stderr: bool,
+    /// Print output to the terminal while capturing it
+    pub tee: bool,
+    /// Maximum number of captured bytes, the rest is dropped
+    pub limit: usize,
+}
+
+/// Limits of a record execution, every unset limit falls back to record defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionLimits {
+    /// Time after which command is killed
+    pub timeout: Option<Duration>,
+    /// Number of additional attempts made after a failed one
+    pub retries: Option<u32>,
+    /// Pause between attempts
+    pub retry_delay: Option<Duration>,
+}
+
+impl ExecutionLimits {
+    /// Fill unset limits with values from `defaults`
+    pub fn or(&self, defaults: &ExecutionLimits) -> ExecutionLimits {
+        ExecutionLimits {
+            timeout: self.timeout.or(defaults.timeout),
+            retries: self.retries.or(defaults.retries),
+            retry_delay: self.retry_delay.or(defaults.retry_delay),
+        }
+    }
+}
+
+/// Command prepared for execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+    /// Command line to run, or script body when command is a script
+    pub command: String,
+    /// Program running the command written into a script file
+    pub interpreter: Option<String>,
+    /// Directory to run command in
+    pub cwd: PathBuf,
+    /// Environment variables added to the inherited environment
+    pub env: BTreeMap<String, String>,
+    pub capture: Option<OutputCapture>,
+    /// Time after which command is killed
+    pub timeout: Option<Duration>,
+    /// Text put in front of every output line, used when output of several commands is mixed
+    pub output_prefix: Option<String>,
+}
+
+impl CommandSpec {
+    pub fn new(command: &str, cwd: &Path) -> CommandSpec {
+        CommandSpec {
+            command: command.to_string(),
+            interpreter: None,
+            cwd: cwd.to_path_buf(),
+            env: BTreeMap::new(),
+            capture: None,
+            timeout: None,
+            output_prefix: None,
+        }
+    }
+
+    /// Scripts are written into a file before execution, either for interpreter or to run their shebang
+    pub fn is_script(&self) -> bool {
+        self.interpreter.is_some() || has_shebang(&self.command)
+    }
+}
+
+/// Outcome of a single command run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionResult {
+    /// Exit code of the process, `None` if it was terminated by a signal
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    /// Whether command was killed because of timeout
+    pub timed_out: bool,
+    /// Captured output, if it was requested
+    pub output: Option<String>,
+}
+
+/// Single entry of the execution history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp (seconds) of the execution start
+    pub timestamp: i64,
+    pub key: String,
+    /// Command that was actually run
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    pub cwd: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+}
+
+impl HistoryEntry {
+    pub fn succeeded(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+impl Display for HistoryEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = match Local.timestamp_opt(self.timestamp, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => self.timestamp.to_string(),
+        };
+        let status = match self.exit_code {
+            _ if self.timed_out => "timeout".to_string(),
+            Some(code) => format!("exit {}", code),
+            None => "killed".to_string(),
+        };
+        write!(
+            f,
+            "{}  {}  [{}]  {:.2}s  {}\n    $ {}",
+            time,
+            self.key,
+            status,
+            self.duration_ms as f64 / 1000.0,
+            self.cwd,
+            self.command
+        )
+    }
+}
+
+/// Process started in background, its start time tells it from a later process which got the same pid
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProcessId {
+    /// Id of the started process, which is also id of its process group
+    pub pid: u32,
+    /// Clock ticks since boot when the process started, `None` for jobs recorded before it was kept.
+    /// Such jobs can not be told from other processes, so they are never signalled
+    #[serde(default)]
+    pub start_time: Option<u64>,
+}
+
+/// Record started in background
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    /// Number identifying the job in job commands
+    pub id: u32,
+    #[serde(flatten)]
+    pub process: ProcessId,
+    /// Unix timestamp (seconds) of the job start
+    pub timestamp: i64,
+    pub key: String,
+    pub command: String,
+    pub cwd: String,
+    /// File collecting stdout and stderr of the job
+    pub log: String,
+}
+
+impl Display for Job {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = match Local.timestamp_opt(self.timestamp, 0).single() {
+            Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => self.timestamp.to_string(),
+        };
+        write!(f, "{}  {}  pid {}  {}\n    $ {}", time, self.key, self.process.pid, self.cwd, self.command)
+    }
+}
+
+/// Outcome of a single workflow step
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepOutcome {
+    /// Step command was run
+    Finished(HistoryEntry),
+    /// Step could not be run at all
+    Failed(String),
+    /// Step was not run because one of previous steps failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepResult {
+    pub key: String,
+    pub outcome: StepOutcome,
+}
+
+impl StepResult {
+    pub fn succeeded(&self) -> bool {
+        match &self.outcome {
+            StepOutcome::Finished(entry) => entry.succeeded(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for StepResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.outcome {
+            StepOutcome::Finished(entry) => {
+                let status = if entry.succeeded() { "ok" } else { "failed" };
+                let code = match entry.exit_code {
+                    _ if entry.timed_out => "timeout".to_string(),
+                    Some(code) => format!("exit {}", code),
+                    None => "killed".to_string(),
+                };
+                let seconds = entry.duration_ms as f64 / 1000.0;
+                write!(f, "[{}] {}  {}  {:.2}s", status, self.key, code, seconds)
+            }
+            StepOutcome::Failed(error) => write!(f, "[failed] {}  {}", self.key, error),
+            StepOutcome::Skipped => write!(f, "[skipped] {}", self.key),
+        }
+    }
+}
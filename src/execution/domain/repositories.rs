@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use crate::execution::domain::entities::{HistoryEntry, Job};
+use crate::shared::domain::error::Error;
+
+pub trait HistoryRepository {
+    /// Append new entry to the end of history
+    fn append(&self, entry: HistoryEntry) -> Result<(), Error>;
+    /// Get all history entries, oldest first
+    fn all(&self) -> Result<Vec<HistoryEntry>, Error>;
+}
+
+impl<T> HistoryRepository for Box<T> where T: HistoryRepository + ?Sized {
+    fn append(&self, entry: HistoryEntry) -> Result<(), Error> {
+        (**self).append(entry)
+    }
+
+    fn all(&self) -> Result<Vec<HistoryEntry>, Error> {
+        (**self).all()
+    }
+}
+
+pub trait JobRepository {
+    /// Register started job
+    fn add(&self, job: Job) -> Result<(), Error>;
+    /// Get all registered jobs, oldest first
+    fn all(&self) -> Result<Vec<Job>, Error>;
+    /// Prepare location of the log file for job with given id
+    fn log_location(&self, id: u32) -> Result<PathBuf, Error>;
+    /// Read everything job has written into its log so far
+    fn read_log(&self, job: &Job) -> Result<String, Error>;
+}
+
+impl<T> JobRepository for Box<T> where T: JobRepository + ?Sized {
+    fn add(&self, job: Job) -> Result<(), Error> {
+        (**self).add(job)
+    }
+
+    fn all(&self) -> Result<Vec<Job>, Error> {
+        (**self).all()
+    }
+
+    fn log_location(&self, id: u32) -> Result<PathBuf, Error> {
+        (**self).log_location(id)
+    }
+
+    fn read_log(&self, job: &Job) -> Result<String, Error> {
+        (**self).read_log(job)
+    }
+}
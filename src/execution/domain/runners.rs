@@ -0,0 +1,21 @@
+use std::path::Path;
+
+use crate::execution::domain::entities::{CommandSpec, ExecutionResult, ProcessId};
+use crate::shared::domain::error::Error;
+
+/// Runner is shared between threads when records are executed in parallel
+pub trait CommandRunner: Sync {
+    /// Run command and wait for its completion
+    fn run(&self, spec: &CommandSpec) -> Result<ExecutionResult, Error>;
+}
+
+/// Starts commands in background and controls them after the start
+pub trait BackgroundRunner {
+    /// Start command without waiting for it, redirecting its stdout and stderr into `log`.
+    /// Returns id of the started process
+    fn spawn(&self, spec: &CommandSpec, log: &Path) -> Result<ProcessId, Error>;
+    /// Whether the started process still runs, not another one which reused its pid
+    fn is_running(&self, process: &ProcessId) -> bool;
+    /// Stop process together with all processes it has started, if it is still the started process
+    fn terminate(&self, process: &ProcessId) -> Result<(), Error>;
+}
@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::execution::domain::entities::{HistoryEntry, Job};
+use crate::execution::domain::repositories::{HistoryRepository, JobRepository};
+use crate::shared::domain::error::Error;
+
+/// Repository to store history in memory. Primary use case - testing
+pub struct InMemoryHistoryRepository {
+    entries: RefCell<Vec<HistoryEntry>>,
+}
+
+impl InMemoryHistoryRepository {
+    pub fn new() -> InMemoryHistoryRepository {
+        InMemoryHistoryRepository {
+            entries: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl HistoryRepository for InMemoryHistoryRepository {
+    fn append(&self, entry: HistoryEntry) -> Result<(), Error> {
+        self.entries.borrow_mut().push(entry);
+        Ok(())
+    }
+
+    fn all(&self) -> Result<Vec<HistoryEntry>, Error> {
+        Ok(self.entries.borrow().clone())
+    }
+}
+
+////////////////////////////////////////////
+/// Repository to store history in append-only file, one JSON entry per line
+pub struct FileHistoryRepository {
+    location: PathBuf,
+}
+
+impl FileHistoryRepository {
+    pub fn new(location: &Path) -> Self {
+        FileHistoryRepository { location: location.to_path_buf() }
+    }
+}
+
+impl HistoryRepository for FileHistoryRepository {
+    fn append(&self, entry: HistoryEntry) -> Result<(), Error> {
+        let line = serde_json::to_string(&entry).map_err(|e| Error::Storage(e.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.location)
+            .map_err(|e| Error::Storage(format!("Could not open history file: {}", e)))?;
+        writeln!(file, "{}", line).map_err(|e| Error::Storage(format!("Could not write history: {}", e)))
+    }
+
+    fn all(&self) -> Result<Vec<HistoryEntry>, Error> {
+        if !self.location.exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(&self.location)
+            .map_err(|e| Error::Storage(format!("Could not read history file: {}", e)))?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|_| Error::Parse(format!("History line \"{}\" has incompatible format", line)))
+            })
+            .collect()
+    }
+}
+
+/// Repository to store jobs in memory. Primary use case - testing
+pub struct InMemoryJobRepository {
+    jobs: RefCell<Vec<Job>>,
+    logs_dir: PathBuf,
+}
+
+impl InMemoryJobRepository {
+    pub fn new(logs_dir: &Path) -> InMemoryJobRepository {
+        InMemoryJobRepository {
+            jobs: RefCell::new(Vec::new()),
+            logs_dir: logs_dir.to_path_buf(),
+        }
+    }
+}
+
+impl JobRepository for InMemoryJobRepository {
+    fn add(&self, job: Job) -> Result<(), Error> {
+        self.jobs.borrow_mut().push(job);
+        Ok(())
+    }
+
+    fn all(&self) -> Result<Vec<Job>, Error> {
+        Ok(self.jobs.borrow().clone())
+    }
+
+    fn log_location(&self, id: u32) -> Result<PathBuf, Error> {
+        Ok(self.logs_dir.join(format!("{}.log", id)))
+    }
+
+    fn read_log(&self, job: &Job) -> Result<String, Error> {
+        Ok(format!("log of {}", job.key))
+    }
+}
+
+////////////////////////////////////////////
+/// Repository to store jobs in a directory: registry file with one JSON job per line
+/// and a separate log file for every job
+pub struct FileJobRepository {
+    location: PathBuf,
+}
+
+impl FileJobRepository {
+    pub fn new(location: &Path) -> Self {
+        FileJobRepository { location: location.to_path_buf() }
+    }
+
+    fn registry(&self) -> PathBuf {
+        self.location.join("jobs")
+    }
+
+    fn create_location(&self) -> Result<(), Error> {
+        fs::create_dir_all(&self.location).map_err(|e| Error::Storage(format!("Could not create jobs directory: {}", e)))
+    }
+}
+
+impl JobRepository for FileJobRepository {
+    fn add(&self, job: Job) -> Result<(), Error> {
+        self.create_location()?;
+        let line = serde_json::to_string(&job).map_err(|e| Error::Storage(e.to_string()))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.registry())
+            .map_err(|e| Error::Storage(format!("Could not open jobs file: {}", e)))?;
+        writeln!(file, "{}", line).map_err(|e| Error::Storage(format!("Could not write job: {}", e)))
+    }
+
+    fn all(&self) -> Result<Vec<Job>, Error> {
+        if !self.registry().exists() {
+            return Ok(Vec::new());
+        }
+        let content = fs::read_to_string(self.registry())
+            .map_err(|e| Error::Storage(format!("Could not read jobs file: {}", e)))?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|_| Error::Parse(format!("Job line \"{}\" has incompatible format", line)))
+            })
+            .collect()
+    }
+
+    fn log_location(&self, id: u32) -> Result<PathBuf, Error> {
+        self.create_location()?;
+        Ok(self.location.join(format!("{}.log", id)))
+    }
+
+    fn read_log(&self, job: &Job) -> Result<String, Error> {
+        fs::read(&job.log)
+            .map(|content| String::from_utf8_lossy(&content).to_string())
+            .map_err(|e| Error::Storage(format!("Could not read log of job {}: {}", job.id, e)))
+    }
+}
@@ -0,0 +1,346 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use subprocess::{Exec, ExitStatus, Popen, PopenConfig, Redirection};
+
+use crate::execution::domain::entities::{CommandSpec, ExecutionResult, OutputCapture, ProcessId};
+use crate::execution::domain::runners::{BackgroundRunner, CommandRunner};
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::temp_file::{create_file, temp_path};
+
+/// Time given to a timed out process to exit after termination request before it is killed
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(3);
+
+/// Runs commands as child processes, splitting command line shell-style unless a shell is given
+#[derive(Default)]
+pub struct SubprocessRunner {
+    /// Shell running commands as `<shell> -c <command>`, so that pipes and globs work
+    shell: Option<String>,
+}
+
+impl SubprocessRunner {
+    pub fn new(shell: Option<String>) -> Self {
+        SubprocessRunner { shell }
+    }
+
+    fn split_command(command: &str) -> Result<Vec<String>, Error> {
+        let cmd_parts: Vec<String> = shlex::split(command).ok_or_else(|| Error::Invalid("Could not split command".to_string()))?;
+        if cmd_parts.is_empty() {
+            return Err(Error::Invalid("Command is empty".to_string()));
+        }
+        Ok(cmd_parts)
+    }
+
+    /// Build program arguments for a command. Scripts are written into `script` file first
+    fn command_line(&self, spec: &CommandSpec, script: &Path) -> Result<Vec<String>, Error> {
+        match &self.shell {
+            Some(shell) if !spec.is_script() => {
+                let mut cmd_parts = Self::split_command(shell)?;
+                cmd_parts.extend(vec!["-c".to_string(), spec.command.to_string()]);
+                return Ok(cmd_parts);
+            }
+            None if !spec.is_script() => return Self::split_command(&spec.command),
+            _ => {}
+        }
+        // Script is written only once into a file nobody else can change, it is closed before execution,
+        // since files open for writing can not be executed
+        create_file(script, &spec.command, 0o700)?;
+        // Script without interpreter has a shebang and is executed directly
+        let mut cmd_parts = match &spec.interpreter {
+            Some(interpreter) => Self::split_command(interpreter)?,
+            None => Vec::new(),
+        };
+        cmd_parts.push(script.to_string_lossy().to_string());
+        Ok(cmd_parts)
+    }
+
+    fn exit_code(status: ExitStatus) -> Option<i32> {
+        match status {
+            ExitStatus::Exited(code) => Some(code as i32),
+            ExitStatus::Other(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Read child output until it is closed, keeping at most `capture.limit` bytes
+    fn collect_output(source: &mut impl Read, capture: &OutputCapture) -> Result<String, Error> {
+        let mut collected: Vec<u8> = Vec::new();
+        let mut buffer = [0u8; 8192];
+        loop {
+            let read = source.read(&mut buffer).map_err(|e| Error::Exec(format!("Could not read output: {}", e)))?;
+            if read == 0 {
+                break;
+            }
+            if capture.tee {
+                let mut stdout = io::stdout();
+                stdout.write_all(&buffer[..read]).and_then(|_| stdout.flush()).ok();
+            }
+            let free = capture.limit.saturating_sub(collected.len());
+            collected.extend_from_slice(&buffer[..read.min(free)]);
+        }
+        // Cut at the limit may split a character, its incomplete part is dropped instead of being replaced
+        if collected.len() == capture.limit {
+            if let Err(e) = std::str::from_utf8(&collected) {
+                if e.error_len().is_none() {
+                    collected.truncate(e.valid_up_to());
+                }
+            }
+        }
+        Ok(String::from_utf8_lossy(&collected).to_string())
+    }
+
+    /// Copy child output line by line to `target`, putting `prefix` in front of every line
+    fn forward_prefixed(source: impl Read, prefix: &str, target: &mut dyn FnMut(&[u8])) {
+        let mut reader = BufReader::new(source);
+        let mut line = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut line) {
+            if read == 0 {
+                break;
+            }
+            if !line.ends_with(b"\n") {
+                line.push(b'\n');
+            }
+            let mut prefixed = prefix.as_bytes().to_vec();
+            prefixed.extend_from_slice(&line);
+            target(&prefixed);
+            line.clear();
+        }
+    }
+
+    /// State and start time of a process, fields 3 and 22 of its stat.
+    /// They are known only where procfs is available
+    fn process_stat(pid: u32) -> Option<(char, u64)> {
+        let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // Fields follow the command name, which is put in parentheses and may contain spaces
+        let fields: Vec<&str> = stat.rsplit(')').next()?.split_whitespace().collect();
+        Some((fields.first()?.chars().next()?, fields.get(19)?.parse().ok()?))
+    }
+
+    /// Wait for process to finish, stopping it once timeout expires.
+    /// Returns exit status and whether process was stopped
+    fn wait(process: &mut Popen, timeout: Option<Duration>) -> Result<(ExitStatus, bool), Error> {
+        let wait_error = |e: subprocess::PopenError| Error::Exec(format!("Failed to wait for command: {}", e));
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return process.wait().map(|status| (status, false)).map_err(wait_error),
+        };
+        if let Some(status) = process.wait_timeout(timeout).map_err(wait_error)? {
+            return Ok((status, false));
+        }
+        process.terminate().ok();
+        let status = match process.wait_timeout(KILL_GRACE_PERIOD).map_err(wait_error)? {
+            Some(status) => status,
+            None => {
+                process.kill().ok();
+                process.wait().map_err(wait_error)?
+            }
+        };
+        Ok((status, true))
+    }
+
+    /// Run prepared program, capturing or forwarding its output as requested
+    fn execute(spec: &CommandSpec, cmd_parts: &[String]) -> Result<ExecutionResult, Error> {
+        let mut exec = Exec::cmd(&cmd_parts[0]).args(&cmd_parts[1..]).cwd(&spec.cwd);
+        for (name, value) in &spec.env {
+            exec = exec.env(name, value);
+        }
+        if let Some(capture) = &spec.capture {
+            exec = exec.stdout(Redirection::Pipe);
+            if capture.stderr {
+                exec = exec.stderr(Redirection::Merge);
+            }
+        } else if spec.output_prefix.is_some() {
+            exec = exec.stdout(Redirection::Pipe).stderr(Redirection::Pipe);
+        }
+
+        let started = Instant::now();
+        let mut process = exec.popen().map_err(|e| Error::Exec(format!("Failed to execute command: {}", e)))?;
+        // Output is read in background, so that timeout can be enforced meanwhile
+        let reader = match &spec.capture {
+            Some(capture) => process.stdout.take().map(|mut stdout| {
+                let capture = capture.clone();
+                thread::spawn(move || Self::collect_output(&mut stdout, &capture))
+            }),
+            None => None,
+        };
+        let mut forwarders = Vec::new();
+        if let (None, Some(prefix)) = (&spec.capture, &spec.output_prefix) {
+            if let Some(stdout) = process.stdout.take() {
+                let prefix = prefix.to_string();
+                forwarders.push(thread::spawn(move || {
+                    Self::forward_prefixed(stdout, &prefix, &mut |line| {
+                        io::stdout().lock().write_all(line).ok();
+                    })
+                }));
+            }
+            if let Some(stderr) = process.stderr.take() {
+                let prefix = prefix.to_string();
+                forwarders.push(thread::spawn(move || {
+                    Self::forward_prefixed(stderr, &prefix, &mut |line| {
+                        io::stderr().lock().write_all(line).ok();
+                    })
+                }));
+            }
+        }
+        let (status, timed_out) = Self::wait(&mut process, spec.timeout)?;
+        for forwarder in forwarders {
+            forwarder.join().ok();
+        }
+        let output = match reader {
+            Some(reader) => Some(reader.join().map_err(|_| Error::Exec("Could not read output".to_string()))??),
+            None => None,
+        };
+        Ok(ExecutionResult {
+            exit_code: Self::exit_code(status),
+            duration: started.elapsed(),
+            timed_out,
+            output,
+        })
+    }
+}
+
+impl CommandRunner for SubprocessRunner {
+    fn run(&self, spec: &CommandSpec) -> Result<ExecutionResult, Error> {
+        // Unique temporary file for a script, commands may run concurrently
+        let script = temp_path("script", "")?;
+        let result = self.command_line(spec, &script).and_then(|cmd_parts| Self::execute(spec, &cmd_parts));
+        if spec.is_script() {
+            fs::remove_file(&script).ok();
+        }
+        result
+    }
+}
+
+impl BackgroundRunner for SubprocessRunner {
+    fn spawn(&self, spec: &CommandSpec, log: &Path) -> Result<ProcessId, Error> {
+        // Script of a job is kept next to its log, since job outlives this process
+        let script = log.with_extension("script");
+        fs::remove_file(&script).ok();
+        let cmd_parts = self.command_line(spec, &script)?;
+        let log = File::create(log).map_err(|e| Error::Exec(format!("Could not create log file: {}", e)))?;
+        let stdin = File::open("/dev/null").map_err(|e| Error::Exec(format!("Could not open /dev/null: {}", e)))?;
+        let mut env = PopenConfig::current_env();
+        env.extend(spec.env.iter().map(|(name, value)| (name.into(), value.into())));
+        // Own process group lets the job outlive the terminal and be stopped together with its children
+        let config = PopenConfig {
+            stdin: Redirection::File(stdin),
+            stdout: Redirection::File(log),
+            stderr: Redirection::Merge,
+            detached: true,
+            env: Some(env),
+            cwd: Some(spec.cwd.clone().into_os_string()),
+            setpgid: true,
+            ..Default::default()
+        };
+        let process = Popen::create(&cmd_parts, config).map_err(|e| Error::Exec(format!("Failed to execute command: {}", e)))?;
+        let pid = process.pid().ok_or_else(|| Error::Exec("Started command has no process id".to_string()))?;
+        // Process is not reaped by this program, so its stat is there even if it has finished already
+        let start_time = Self::process_stat(pid).map(|(_, start_time)| start_time);
+        Ok(ProcessId { pid, start_time })
+    }
+
+    /// Finished process stays a zombie until its parent reaps it, it is not running anymore though
+    fn is_running(&self, process: &ProcessId) -> bool {
+        match (process.start_time, Self::process_stat(process.pid)) {
+            (Some(started), Some((state, start_time))) => started == start_time && state != 'Z',
+            _ => false,
+        }
+    }
+
+    fn terminate(&self, process: &ProcessId) -> Result<(), Error> {
+        if !self.is_running(process) {
+            return Err(Error::Invalid(format!("Process {} is not the started one anymore", process.pid)));
+        }
+        // Negative pid addresses the whole process group
+        match unsafe { libc::kill(-(process.pid as libc::pid_t), libc::SIGTERM) } {
+            0 => Ok(()),
+            _ => Err(Error::Exec(format!("Could not stop process {}: {}", process.pid, io::Error::last_os_error()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn captures_output_up_to_limit() {
+        let mut spec = CommandSpec::new("echo hello world", Path::new("/"));
+        spec.capture = Some(OutputCapture { stderr: false, tee: false, limit: 5 });
+        let result = SubprocessRunner::default().run(&spec).unwrap();
+        assert_eq!(result.exit_code, Some(0));
+        assert_eq!(result.output, Some("hello".to_string()));
+
+        // "é" takes two bytes, the limit falls between them
+        let mut spec = CommandSpec::new("echo café", Path::new("/"));
+        spec.capture = Some(OutputCapture { stderr: false, tee: false, limit: 4 });
+        let result = SubprocessRunner::default().run(&spec).unwrap();
+        assert_eq!(result.output, Some("caf".to_string()));
+    }
+
+    #[test]
+    fn runs_scripts_with_interpreter_or_shebang() {
+        let capture = Some(OutputCapture { stderr: false, tee: false, limit: 1024 });
+        let mut spec = CommandSpec::new("echo first\necho second", Path::new("/"));
+        spec.interpreter = Some("sh -e".to_string());
+        spec.capture = capture.clone();
+        let result = SubprocessRunner::default().run(&spec).unwrap();
+        assert_eq!(result.output, Some("first\nsecond\n".to_string()));
+
+        let mut spec = CommandSpec::new("#!/bin/sh\necho \"$0\" | grep -c '/dumpb-[0-9]*/script-'", Path::new("/"));
+        spec.capture = capture;
+        let result = SubprocessRunner::default().run(&spec).unwrap();
+        assert_eq!(result.output, Some("1\n".to_string()));
+    }
+
+    #[test]
+    fn runs_commands_with_configured_shell() {
+        let mut spec = CommandSpec::new("echo hello | tr h j", Path::new("/"));
+        spec.capture = Some(OutputCapture { stderr: false, tee: false, limit: 1024 });
+        let result = SubprocessRunner::new(Some("sh".to_string())).run(&spec).unwrap();
+        assert_eq!(result.output, Some("jello\n".to_string()));
+    }
+
+    #[test]
+    fn prefixes_every_output_line() {
+        let mut lines = Vec::new();
+        let source: &[u8] = b"first\nsecond";
+        SubprocessRunner::forward_prefixed(source, "[key] ", &mut |line| lines.push(line.to_vec()));
+        assert_eq!(lines, vec![b"[key] first\n".to_vec(), b"[key] second\n".to_vec()]);
+    }
+
+    #[test]
+    fn kills_command_after_timeout() {
+        let mut spec = CommandSpec::new("sleep 5", Path::new("/"));
+        spec.timeout = Some(Duration::from_millis(100));
+        let result = SubprocessRunner::default().run(&spec).unwrap();
+        assert!(result.timed_out);
+        assert!(result.duration < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn background_command_writes_log_and_can_be_stopped() {
+        let log = std::env::temp_dir().join(format!("dumpb-job-{}.log", std::process::id()));
+        let spec = CommandSpec::new("sh -c 'echo started; exec sleep 5'", Path::new("/"));
+        let runner = SubprocessRunner::default();
+        let process = runner.spawn(&spec, &log).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        assert!(runner.is_running(&process));
+        // Another process which got the same pid later is left alone
+        let reused = ProcessId { start_time: process.start_time.map(|start_time| start_time + 1), ..process };
+        assert!(!runner.is_running(&reused));
+        assert!(runner.terminate(&reused).is_err());
+        assert!(!runner.is_running(&ProcessId { start_time: None, ..process }));
+
+        runner.terminate(&process).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        assert!(!runner.is_running(&process));
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "started\n");
+        std::fs::remove_file(&log).ok();
+    }
+}
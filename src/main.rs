@@ -1,85 +1,685 @@
+mod execution;
 mod record;
 mod shared;
 
-use subprocess::Exec;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 
-use dirs;
-use shlex;
 use structopt::StructOpt;
 
+use crate::execution::application::{
+    queries::{
+        ExecuteRecordQuery, GetJobLogQuery, KillJobQuery, ListHistoryQuery, ListJobsQuery, OutputTarget,
+        RerunHistoryQuery, RunParallelQuery, RunWorkflowQuery, SaveOutput, StartJobQuery,
+    },
+    services::{
+        ExecuteRecordService, GetJobLogService, KillJobService, ListHistoryService, ListJobsService,
+        RerunHistoryService, RunParallelService, RunWorkflowService, StartJobService,
+    },
+};
+use crate::execution::domain::{
+    entities::{ExecutionLimits, HistoryEntry, OutputCapture, StepOutcome, StepResult},
+    repositories::{HistoryRepository, JobRepository},
+};
+use crate::execution::infrastructure::{
+    repositories::{FileHistoryRepository, FileJobRepository},
+    runners::SubprocessRunner,
+};
 use crate::record::application::{
-    queries::{AddNewRecordQuery, DeleteRecordQuery, GetRecordQuery, ListRecordsQuery},
+    queries::{
+        AddNewRecordQuery, AddWorkflowQuery, DeleteRecordQuery, GetRecordQuery, ImportRecordsQuery,
+        ListRecordsQuery, SearchRecordsQuery, SuggestRecordsQuery, UpdateRecordQuery,
+    },
     services::{
-        AddNewRecordService, ClearRecordsService, GetRecordService, ListRecordsService, ListResult,
+        AddNewRecordService, AddWorkflowService, ClearRecordsService, ExportRecordsService,
+        GetRecordService, ImportRecordsService, ListRecordsService, ListResult, SearchRecordsService,
+        SuggestRecordsService, Suggestion, UpdateRecordService,
     },
 };
+use crate::record::domain::entities::RecordMetadata;
+use crate::record::domain::fuzzy::score;
 use crate::record::domain::repositories::RecordRepository;
+use crate::record::domain::scripts::guess_interpreter;
+use crate::record::domain::search::SearchPattern;
+use crate::record::infrastructure::cheat_sheet::cheat_sheet;
+use crate::record::infrastructure::codecs::{decode, encode, Format};
+use crate::record::infrastructure::importers::{convert, Conversion};
+use crate::record::infrastructure::output::{details, keys_output, list_output, record_output, value_output, OutputFormat};
 use crate::record::infrastructure::repositories::KVFileDatabaseRepository;
-use crate::shared::infrastructure::cli::DumpBufferCLI;
+use crate::record::infrastructure::shell_script::{export_script, init_script, prompt_line};
+use crate::record::infrastructure::template::Template;
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::cli::{is_command, ConfigCommand, DumpBufferCLI, FlowCommand, HistoryCommand};
+use crate::shared::infrastructure::completions::{completion_script, COMPLETE_COMMAND};
+use crate::shared::infrastructure::config::{home_dir, Backend, Config};
+use crate::shared::infrastructure::editor::edit_text;
 use crate::shared::infrastructure::file_db::KVFileDatabase;
+use crate::shared::infrastructure::picker::{pick, PickerItem};
+use crate::shared::infrastructure::prompt::{ask, ask_terminal, confirmed, parse_selection};
+use crate::shared::infrastructure::shell_history::ShellHistory;
+use crate::shared::infrastructure::terminal::{copy_to_clipboard, highlight, highlight_lines, terminal_width};
+use crate::shared::infrastructure::tui::run_tui;
+
+/// Number of the most repeated commands offered for interactive import
+const IMPORT_CANDIDATES: usize = 30;
+
+/// Directory with all files of DumpBuffer, created if it does not exist yet
+fn setup_store(config: &Config) -> Result<PathBuf, Error> {
+    let store = config.store_path()?;
+    fs::create_dir_all(&store).map_err(|e| Error::Storage(format!("Could not create {}: {}", store.display(), e)))?;
+    Ok(store)
+}
+
+fn setup_repository(config: &Config, store: &Path) -> Result<Box<dyn RecordRepository>, Error> {
+    match config.backend()? {
+        Backend::File => {
+            let db = KVFileDatabase::new(&store.join(".dumpb_store"))?;
+            let metadata_db = KVFileDatabase::new(&store.join(".dumpb_meta"))?;
+            Ok(Box::new(KVFileDatabaseRepository::new(db, metadata_db)))
+        }
+    }
+}
+
+fn setup_history_repository(store: &Path) -> Box<dyn HistoryRepository> {
+    Box::new(FileHistoryRepository::new(&store.join(".dumpb_history")))
+}
+
+fn setup_job_repository(store: &Path) -> Box<dyn JobRepository> {
+    Box::new(FileJobRepository::new(&store.join(".dumpb_jobs")))
+}
+
+/// Arguments with alias of the subcommand replaced, e.g. `dumpb ls` becomes `dumpb list --format table`
+fn expand_alias(mut args: Vec<OsString>, config: &Config) -> Result<Vec<OsString>, Error> {
+    let name = match args.get(1).and_then(|name| name.to_str()) {
+        Some(name) => name.to_string(),
+        None => return Ok(args),
+    };
+    if let Some(command) = config.alias(&name)? {
+        // Commands win over aliases with the same name
+        if !is_command(&name) {
+            args.splice(1..2, command.into_iter().map(OsString::from));
+        }
+    }
+    Ok(args)
+}
+
+/// Format given on the command line, the configured one, or the default of the command
+fn output_format(format: &Option<OutputFormat>, config: &Config, default: OutputFormat) -> Result<OutputFormat, Error> {
+    match (format, config.get("output.format")?) {
+        (Some(format), _) => Ok(*format),
+        (None, Some(configured)) => configured.parse().map_err(Error::Invalid),
+        (None, None) => Ok(default),
+    }
+}
+
+fn current_dir() -> Result<std::path::PathBuf, Error> {
+    env::current_dir().map_err(|e| Error::Storage(format!("Could not determine current directory: {}", e)))
+}
+
+/// Read value byte by byte, it is stored without any changes
+fn read_value(source: &mut impl Read, name: &str) -> Result<String, Error> {
+    let mut content = Vec::new();
+    source
+        .read_to_end(&mut content)
+        .map_err(|e| Error::Storage(format!("Could not read {}: {}", name.to_lowercase(), e)))?;
+    if content.is_empty() {
+        return Err(Error::Invalid(format!("{} is empty", name)));
+    }
+    String::from_utf8(content).map_err(|_| Error::Parse(format!("{} is not valid UTF-8 text", name)))
+}
+
+fn last_shell_command() -> Result<String, Error> {
+    ShellHistory::detect(&home_dir()?)?.last_command()
+}
+
+/// Let user pick suggested commands and adjust their keys
+fn choose_suggestions(suggestions: Vec<Suggestion>) -> Result<Vec<Suggestion>, Error> {
+    for (number, suggestion) in suggestions.iter().enumerate() {
+        // Only the first line of multi-line commands is shown
+        let mut lines = suggestion.command.lines();
+        let more = if lines.clone().count() > 1 { " ..." } else { "" };
+        println!("{:>3}. [{}x] {}{}", number + 1, suggestion.count, lines.next().unwrap_or_default(), more);
+    }
+    let selection = ask("Commands to import (e.g. \"1 3 5-7\", empty to cancel): ")?;
+    let mut chosen = Vec::new();
+    for index in parse_selection(&selection, suggestions.len())? {
+        let suggestion = &suggestions[index];
+        let key = ask(&format!("Key for \"{}\" [{}]: ", suggestion.command, suggestion.key))?;
+        let key = if key.is_empty() { suggestion.key.to_string() } else { key };
+        chosen.push(Suggestion { key, ..suggestion.clone() });
+    }
+    Ok(chosen)
+}
 
-fn setup_repository() -> Result<Box<dyn RecordRepository>, String> {
-    let mut db_path = dirs::home_dir().expect("Could not determine user home directory");
-    db_path.push(".dumpb_store");
-    let db = KVFileDatabase::new(&db_path)?;
-    Ok(Box::new(KVFileDatabaseRepository::new(db)))
+/// Message to print and exit code of the application
+struct Response {
+    message: String,
+    exit_code: i32,
 }
 
-fn execute_command(command: String) -> Result<(), String> {
-    let cmd_parts: Vec<String> = shlex::split(&command).ok_or("Could not split command")?;
-    Exec::cmd(cmd_parts[0].to_string())
-        .args(&cmd_parts[1..])
-        .join()
-        .expect("Failed to execute command");
-    Ok(())
-} 
+impl From<String> for Response {
+    fn from(message: String) -> Self {
+        Response { message, exit_code: 0 }
+    }
+}
+
+impl From<&HistoryEntry> for Response {
+    /// Exit code of executed command becomes exit code of the application
+    fn from(entry: &HistoryEntry) -> Self {
+        let exit_code = match entry.exit_code {
+            _ if entry.timed_out => 124,
+            Some(code) => code,
+            None => 1,
+        };
+        Response { message: "".to_string(), exit_code }
+    }
+}
+
+impl Response {
+    /// Action was declined by the user, exit code is the same as for cancelled picking
+    fn cancelled() -> Self {
+        Response { message: "Cancelled".to_string(), exit_code: 130 }
+    }
+}
 
-fn handle(args: &DumpBufferCLI, repo: Box<dyn RecordRepository>) -> Result<String, String> {
+fn summary(title: &str, results: &[StepResult]) -> Response {
+    let mut lines = vec![title.to_string()];
+    lines.extend(results.iter().map(|result| format!("  {}", result)));
+    // The first failure determines exit code of the whole execution
+    let exit_code = match results.iter().find(|result| !result.succeeded()) {
+        Some(StepResult { outcome: StepOutcome::Finished(entry), .. }) => Response::from(entry).exit_code,
+        Some(_) => 1,
+        None => 0,
+    };
+    Response { message: lines.join("\n"), exit_code }
+}
+
+/// How a record is run in the foreground by `exec` and `pick --exec`
+struct RunOptions {
+    save_output: Option<SaveOutput>,
+    limits: ExecutionLimits,
+    continue_on_error: bool,
+}
+
+/// Run a record or every step of a workflow in the foreground
+fn run_record(
+    key: &str,
+    options: RunOptions,
+    repo: &dyn RecordRepository,
+    history: &dyn HistoryRepository,
+    runner: &SubprocessRunner,
+) -> Result<Response, Error> {
+    let record = GetRecordService::new(repo).run(&GetRecordQuery::new(key.to_string()))?;
+    if record.metadata.is_workflow() {
+        if options.save_output.is_some() {
+            return Err(Error::Invalid("Output of a workflow can not be saved".to_string()));
+        }
+        let query = RunWorkflowQuery::new(key.to_string(), current_dir()?, options.continue_on_error, options.limits);
+        let service = RunWorkflowService::new(repo, history, runner);
+        let title = format!("Workflow \"{}\" summary:", key);
+        return service.run(&query).map(|results| summary(&title, &results));
+    }
+    let query = ExecuteRecordQuery::new(key.to_string(), current_dir()?, options.save_output).with_limits(options.limits);
+    let service = ExecuteRecordService::new(repo, history, runner);
+    service.run(&query).map(|entry| Response {
+        message: match &query.save_output {
+            Some(SaveOutput { target: OutputTarget::Record(output_key), .. }) => {
+                format!("Saved output into record with key \"{}\"", output_key)
+            }
+            Some(SaveOutput { target: OutputTarget::LastRun, .. }) => {
+                format!("Saved output into last-run metadata of \"{}\"", key)
+            }
+            None => "".to_string(),
+        },
+        ..Response::from(&entry)
+    })
+}
+
+fn handle(
+    args: &DumpBufferCLI,
+    repo: &dyn RecordRepository,
+    history: Box<dyn HistoryRepository>,
+    jobs: Box<dyn JobRepository>,
+    config: &mut Config,
+) -> Result<Response, Error> {
+    let runner = SubprocessRunner::new(config.get("exec.shell")?);
     match args {
-        DumpBufferCLI::Add { key, value: _ } => {
-            let joined_value = args.joined_value(" ").unwrap();
-            let query = AddNewRecordQuery::new(key.to_string(), joined_value);
-            let service = AddNewRecordService::new(&repo);
+        DumpBufferCLI::Add {
+            description,
+            cwd,
+            env,
+            tags,
+            timeout,
+            retries,
+            retry_delay,
+            interpreter,
+            from_file,
+            last,
+            key,
+            value,
+        } => {
+            let mut metadata = RecordMetadata::default();
+            let joined_value = match from_file {
+                Some(path) => {
+                    let source = format!("File {}", path.display());
+                    let mut file = fs::File::open(path).map_err(|e| Error::Storage(format!("Could not open {}: {}", source, e)))?;
+                    let content = read_value(&mut file, &source)?;
+                    metadata.interpreter = guess_interpreter(path, &content);
+                    content
+                }
+                None if *last => last_shell_command()?,
+                None if value.len() == 1 && value[0] == "-" => read_value(&mut io::stdin(), "Stdin")?,
+                None => args.joined_value(" ").unwrap(),
+            };
+            if interpreter.is_some() {
+                metadata.interpreter = interpreter.clone();
+            }
+            metadata.description = description.clone();
+            if let Some(cwd) = cwd {
+                metadata.cwd = Some(current_dir()?.join(cwd).to_string_lossy().to_string());
+            }
+            metadata.env = env.iter().cloned().collect();
+            metadata.tags = tags.clone();
+            metadata.timeout_ms = timeout.map(|timeout| timeout.as_millis() as u64);
+            metadata.retries = *retries;
+            metadata.retry_delay_ms = retry_delay.map(|delay| delay.as_millis() as u64);
+            let query = AddNewRecordQuery::new(key.to_string(), joined_value).with_metadata(metadata);
+            let service = AddNewRecordService::new(repo);
             service
                 .run(&query)
                 .and_then(|_| Ok(format!("Successfully added new value with key \"{}\"", key)))
+                .map(Response::from)
         }
-        DumpBufferCLI::Get { key } => {
-            let query = GetRecordQuery::new(key.to_string());
-            let service = GetRecordService::new(&repo);
+        DumpBufferCLI::SaveLast { key } => {
+            let query = AddNewRecordQuery::new(key.to_string(), last_shell_command()?);
+            let service = AddNewRecordService::new(repo);
             service
                 .run(&query)
-                .and_then(|record| Ok(record.value.to_string()))
-        }
-        DumpBufferCLI::List { keys_only } => {
-            let query = ListRecordsQuery::new(keys_only.clone());
-            let service = ListRecordsService::new(&repo);
-            match service.run(&query) {
-                Ok(ListResult::KeyView(keys)) => Ok(format!("[\n  {}\n]", keys.join(",\n  "))),
-                Ok(ListResult::RecordView(records)) => {
-                    let repr: Vec<String> = records.iter().map(|v| v.to_string()).collect();
-                    Ok(format!("[\n{}\n]", repr.join(",\n")))
+                .map(|_| Response::from(format!("Successfully added \"{}\" with key \"{}\"", query.value, key)))
+        }
+        DumpBufferCLI::ImportHistory { top, min_length, shell, history_file } => {
+            let commands = ShellHistory::locate(*shell, history_file.as_deref(), &home_dir()?)?.commands()?;
+            let limit = top.unwrap_or(IMPORT_CANDIDATES);
+            let query = SuggestRecordsQuery::new(commands, *min_length, limit);
+            let suggestions = SuggestRecordsService::new(repo).run(&query)?;
+            if suggestions.is_empty() {
+                return Ok(Response::from("No commands to import".to_string()));
+            }
+            let chosen = match top {
+                Some(_) => suggestions,
+                None => choose_suggestions(suggestions)?,
+            };
+            let service = AddNewRecordService::new(repo);
+            let (mut lines, mut imported) = (Vec::new(), 0);
+            for suggestion in &chosen {
+                let query = AddNewRecordQuery::new(suggestion.key.to_string(), suggestion.command.to_string());
+                match service.run(&query) {
+                    Ok(_) => {
+                        imported += 1;
+                        lines.push(format!("  {}: {}", suggestion.key, suggestion.command));
+                    }
+                    Err(e) => lines.push(format!("  [ERROR] {}: {}", suggestion.key, e)),
                 }
-                Err(e) => Err(e),
             }
+            lines.insert(0, format!("Imported {} of {} commands:", imported, chosen.len()));
+            Ok(Response::from(lines.join("\n")))
+        }
+        DumpBufferCLI::Get { key, fuzzy, format, template } => {
+            let template = template.as_deref().map(Template::parse).transpose()?;
+            let query = GetRecordQuery::new(key.to_string()).fuzzy(*fuzzy);
+            let record = GetRecordService::new(repo).run(&query)?;
+            if let Some(template) = template {
+                return Ok(Response::from(template.render(&record)));
+            }
+            // Setting is not applied, scripts read the bare value with `$(dumpb get key)`
+            value_output(&record, format.unwrap_or(OutputFormat::Plain), terminal_width()).map(Response::from)
+        }
+        DumpBufferCLI::Show { key, fuzzy, format } => {
+            let query = GetRecordQuery::new(key.to_string()).fuzzy(*fuzzy);
+            let record = GetRecordService::new(repo).run(&query)?;
+            record_output(&record, output_format(format, config, OutputFormat::Plain)?, terminal_width()).map(Response::from)
+        }
+        DumpBufferCLI::Edit { key, fuzzy } => {
+            let record = GetRecordService::new(repo).run(&GetRecordQuery::new(key.to_string()).fuzzy(*fuzzy))?;
+            let value = edit_text(&record.value, config.get("editor.command")?.as_deref())?;
+            if value == record.value {
+                return Ok(Response::from(format!("Value of \"{}\" was not changed", record.key)));
+            }
+            let query = UpdateRecordQuery::new(record.key.to_string(), value);
+            UpdateRecordService::new(repo).run(&query)?;
+            Ok(Response::from(format!("Updated value of \"{}\"", record.key)))
+        }
+        DumpBufferCLI::List { keys_only, format, template } => {
+            let template = template.as_deref().map(Template::parse).transpose()?;
+            let format = output_format(format, config, OutputFormat::Json)?;
+            match ListRecordsService::new(repo).run(&ListRecordsQuery::new(*keys_only))? {
+                ListResult::KeyView(mut keys) => {
+                    keys.sort();
+                    keys_output(&keys, format, terminal_width())
+                }
+                ListResult::RecordView(mut records) => {
+                    records.sort_by(|a, b| a.key.cmp(&b.key));
+                    match template {
+                        Some(template) => Ok(template.render_all(&records)),
+                        None => list_output(&records, format, terminal_width()),
+                    }
+                }
+            }
+            .map(Response::from)
+        }
+        DumpBufferCLI::Pick { query, copy, exec, line } => {
+            let mut records = match ListRecordsService::new(repo).run(&ListRecordsQuery::new(false))? {
+                ListResult::RecordView(records) => records,
+                ListResult::KeyView(_) => vec![],
+            };
+            records.sort_by(|a, b| a.key.cmp(&b.key));
+            let items: Vec<PickerItem> = records
+                .iter()
+                .map(|record| PickerItem {
+                    label: format!("{}  {}", record.key, record.value.lines().next().unwrap_or_default()),
+                    text: format!("{} {}", record.key, record.value),
+                    preview: details(record),
+                })
+                .collect();
+            let record = match pick(&items, query, &score)? {
+                Some(index) => &records[index],
+                // Exit code of cancelled fzf, so that shell widgets can tell it from an empty value
+                None => return Ok(Response { message: String::new(), exit_code: 130 }),
+            };
+            if *exec {
+                if !confirmed(config, "exec", &format!("Execute \"{}\"?", record.key))? {
+                    return Ok(Response::cancelled());
+                }
+                let options = RunOptions { save_output: None, limits: ExecutionLimits::default(), continue_on_error: false };
+                return run_record(&record.key, options, repo, history.as_ref(), &runner);
+            }
+            if let Some(shell) = line {
+                let mut value_of = |name: &str| ask_terminal(&format!("{}: ", name));
+                return prompt_line(*shell, record, &mut value_of).map(Response::from);
+            }
+            if *copy {
+                copy_to_clipboard(&record.value)?;
+                return Ok(Response::from(format!("Copied value of \"{}\" into clipboard", record.key)));
+            }
+            Ok(Response::from(record.value.to_string()))
+        }
+        DumpBufferCLI::Tui => {
+            run_tui(repo, &history, &runner, &details, config)?;
+            Ok(Response::from(String::new()))
+        }
+        DumpBufferCLI::Completions { shell } => completion_script(*shell).map(Response::from),
+        DumpBufferCLI::ShellInit { shell } => Ok(Response::from(init_script(*shell))),
+        DumpBufferCLI::Search { pattern, regex, ignore_case, template } => {
+            let template = template.as_deref().map(Template::parse).transpose()?;
+            let pattern = SearchPattern::new(pattern, *regex, *ignore_case)?;
+            let records = SearchRecordsService::new(repo).run(&SearchRecordsQuery::new(pattern.clone()))?;
+            // Scripts get no output instead of a message when nothing is found
+            if let Some(template) = template {
+                return Ok(Response::from(template.render_all(&records)));
+            }
+            if records.is_empty() {
+                return Ok(Response::from("No records found".to_string()));
+            }
+            let colour = config.use_color()?;
+            let show = |text: &str| if colour { highlight(text, &pattern.find(text)) } else { text.to_string() };
+            let mut lines = Vec::new();
+            for record in &records {
+                lines.push(show(&record.key));
+                if let Some(description) = &record.metadata.description {
+                    lines.push(format!("    # {}", show(description)));
+                }
+                // Matches are found in the whole value, so that they may span lines, but colour is reset on every line
+                let value = if colour {
+                    highlight_lines(&record.value, &pattern.find(&record.value))
+                } else {
+                    record.value.lines().map(str::to_string).collect()
+                };
+                lines.extend(value.iter().map(|line| format!("    {}", line)));
+            }
+            Ok(Response::from(lines.join("\n")))
+        }
+        DumpBufferCLI::Export { format, group_by, shell, output } => {
+            let records = ExportRecordsService::new(repo).run()?;
+            let content = match shell {
+                Some(shell) => export_script(&records, *shell),
+                None => {
+                    let format = format
+                        .or_else(|| output.as_deref().and_then(Format::from_path))
+                        .unwrap_or(Format::Json);
+                    match format {
+                        Format::Markdown => cheat_sheet(&records, *group_by),
+                        _ => encode(&records, format)?,
+                    }
+                }
+            };
+            match output {
+                Some(path) => fs::write(path, content)
+                    .map(|_| Response::from(format!("Exported {} records into {}", records.len(), path.display())))
+                    .map_err(|e| Error::Storage(format!("Could not write {}: {}", path.display(), e))),
+                None => Ok(Response::from(content)),
+            }
+        }
+        DumpBufferCLI::Import { file, format, from, on_conflict, dry_run } => {
+            let content = if file.as_os_str() == "-" {
+                read_value(&mut io::stdin(), "Stdin")?
+            } else {
+                let source = format!("File {}", file.display());
+                read_value(&mut fs::File::open(file).map_err(|e| Error::Storage(format!("Could not open {}: {}", source, e)))?, &source)?
+            };
+            let conversion = match from {
+                Some(source) => convert(&content, *source)?,
+                None => {
+                    let format = format.unwrap_or_else(|| Format::detect(file, &content));
+                    Conversion { records: decode(&content, format)?, unconverted: vec![] }
+                }
+            };
+            let query = ImportRecordsQuery::new(conversion.records, *on_conflict, *dry_run);
+            let summary = ImportRecordsService::new(repo).run(&query)?;
+            let mut lines = vec![if *dry_run { "Dry run, nothing was changed:" } else { "Import summary:" }.to_string()];
+            lines.extend(summary.added.iter().map(|key| format!("  added {}", key)));
+            lines.extend(summary.overwritten.iter().map(|key| format!("  overwritten {}", key)));
+            lines.extend(summary.renamed.iter().map(|(from, to)| format!("  renamed {} -> {}", from, to)));
+            lines.extend(summary.skipped.iter().map(|key| format!("  skipped {}", key)));
+            if !conversion.unconverted.is_empty() {
+                lines.push("Could not convert:".to_string());
+                lines.extend(conversion.unconverted.iter().map(|item| format!("  {}", item)));
+            }
+            Ok(Response::from(lines.join("\n")))
         }
         DumpBufferCLI::Delete { key, all } => {
+            let (action, question) = match key {
+                Some(key) if !*all => ("remove", format!("Remove record \"{}\"?", key)),
+                _ => ("clear", "Remove all records?".to_string()),
+            };
+            if !confirmed(config, action, &question)? {
+                return Ok(Response::cancelled());
+            }
             let query = DeleteRecordQuery::new(key, all.clone());
-            let service = ClearRecordsService::new(&repo);
-            service.run(&query)
+            let service = ClearRecordsService::new(repo);
+            service.run(&query).map(Response::from)
+        }
+        DumpBufferCLI::Exec {
+            keys,
+            fuzzy,
+            save_output,
+            tee,
+            with_stderr,
+            output_limit,
+            timeout,
+            retries,
+            retry_delay,
+            continue_on_error,
+            parallel,
+            tag,
+            jobs: max_jobs,
+            detach,
+        } => {
+            let save_output = save_output.as_ref().map(|output_key| SaveOutput {
+                target: match output_key {
+                    Some(output_key) => OutputTarget::Record(output_key.to_string()),
+                    None => OutputTarget::LastRun,
+                },
+                capture: OutputCapture { stderr: *with_stderr, tee: *tee, limit: *output_limit },
+            });
+            let limits = ExecutionLimits { timeout: *timeout, retries: *retries, retry_delay: *retry_delay };
+            let keys: Vec<String> = keys
+                .iter()
+                .map(|key| {
+                    let query = GetRecordQuery::new(key.to_string()).fuzzy(*fuzzy);
+                    let record = GetRecordService::new(repo).run(&query)?;
+                    // Output of the command goes to stdout, so the guess is shown apart from it
+                    if record.key != *key {
+                        eprintln!("Running \"{}\" for \"{}\"", record.key, key);
+                    }
+                    Ok(record.key)
+                })
+                .collect::<Result<_, Error>>()?;
+            let targets = match tag {
+                Some(tag) if keys.is_empty() => format!("records tagged \"{}\"", tag),
+                _ => keys.iter().map(|key| format!("\"{}\"", key)).collect::<Vec<_>>().join(", "),
+            };
+            if !confirmed(config, "exec", &format!("Execute {}?", targets))? {
+                return Ok(Response::cancelled());
+            }
+            if *parallel {
+                if save_output.is_some() {
+                    return Err(Error::Invalid("Output can not be saved when records are executed in parallel".to_string()));
+                }
+                let query = RunParallelQuery::new(keys.clone(), tag.clone(), current_dir()?, *max_jobs, limits);
+                let service = RunParallelService::new(repo, &history, &runner);
+                return service.run(&query).map(|results| summary("Parallel execution summary:", &results));
+            }
+            let key = match keys.as_slice() {
+                [key] => key.to_string(),
+                _ => return Err(Error::Invalid("Several records can be executed only with --parallel".to_string())),
+            };
+            if *detach {
+                let query = StartJobQuery::new(key.to_string(), current_dir()?);
+                let service = StartJobService::new(repo, &jobs, &runner);
+                return service.run(&query).map(|job| {
+                    Response::from(format!("Started job {} (pid {}), output is written into {}", job.id, job.process.pid, job.log))
+                });
+            }
+            let options = RunOptions { save_output, limits, continue_on_error: *continue_on_error };
+            run_record(&key, options, repo, history.as_ref(), &runner)
+        }
+        DumpBufferCLI::Flow { command: FlowCommand::Add { key, steps } } => {
+            let query = AddWorkflowQuery::new(key.to_string(), steps.clone());
+            let service = AddWorkflowService::new(repo);
+            service
+                .run(&query)
+                .map(|_| Response::from(format!("Successfully added new workflow with key \"{}\"", key)))
+        }
+        DumpBufferCLI::History { key: _, failed: _, command: Some(HistoryCommand::Rerun { number }) } => {
+            let query = RerunHistoryQuery::new(*number);
+            let service = RerunHistoryService::new(&history, &runner);
+            service.run(&query).map(|entry| Response::from(&entry))
         }
-        DumpBufferCLI::Exec { key } => {
-            handle(&DumpBufferCLI::Get{ key: key.to_string() }, repo)
-                .and_then(|val| execute_command(val))
-                .and_then(|_| Ok("".to_string()))
+        DumpBufferCLI::History { key, failed, command: None } => {
+            let query = ListHistoryQuery::new(key, *failed);
+            let service = ListHistoryService::new(&history);
+            service.run(&query).map(|entries| {
+                entries
+                    .iter()
+                    .map(|(number, entry)| format!("{:>4}  {}", number, entry))
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+            .map(Response::from)
         }
+        DumpBufferCLI::Jobs { running } => {
+            let query = ListJobsQuery::new(*running);
+            let service = ListJobsService::new(&jobs, &runner);
+            service.run(&query).map(|jobs| {
+                jobs.iter()
+                    .map(|(job, running)| {
+                        let status = if *running { "running" } else { "finished" };
+                        format!("{:>4}  {:<8}  {}", job.id, status, job)
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            })
+            .map(Response::from)
+        }
+        DumpBufferCLI::Logs { job } => {
+            let query = GetJobLogQuery::new(*job);
+            let service = GetJobLogService::new(&jobs);
+            service.run(&query).map(Response::from)
+        }
+        DumpBufferCLI::Kill { job } => {
+            let query = KillJobQuery::new(*job);
+            let service = KillJobService::new(&jobs, &runner);
+            service
+                .run(&query)
+                .map(|job| Response::from(format!("Stopped job {} running \"{}\"", job.id, job.key)))
+        }
+        DumpBufferCLI::Config { command } => manage_config(command, config),
     }
 }
 
+fn manage_config(command: &ConfigCommand, config: &mut Config) -> Result<Response, Error> {
+    match command {
+        ConfigCommand::Get { name } => Ok(Response::from(config.get(name)?.unwrap_or_default())),
+        ConfigCommand::Set { name, value } => {
+            config.set(name, value).map(|_| Response::from(format!("Set {} to \"{}\"", name, value)))
+        }
+        ConfigCommand::Unset { name } => config.unset(name).map(|_| Response::from(format!("Unset {}", name))),
+        ConfigCommand::List => {
+            let settings = config.list()?;
+            let lines: Vec<String> = settings.iter().map(|(name, value)| format!("{} = {}", name, value)).collect();
+            Ok(Response::from(lines.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n")))
+        }
+    }
+}
+
+/// Keys of all records one per line, asked for by completion scripts
+fn complete_keys() -> Result<Response, Error> {
+    let config = Config::load()?;
+    let repo = setup_repository(&config, &setup_store(&config)?)?;
+    match ListRecordsService::new(&repo).run(&ListRecordsQuery::new(true))? {
+        ListResult::KeyView(keys) => Ok(Response::from(keys.join("\n"))),
+        ListResult::RecordView(_) => Ok(Response::from(String::new())),
+    }
+}
+
+fn run() -> Result<Response, Error> {
+    let mut config = Config::load()?;
+    let args = DumpBufferCLI::from_iter(expand_alias(env::args_os().collect(), &config)?);
+    let store = setup_store(&config)?;
+    let repo = setup_repository(&config, &store)?;
+    let response = handle(&args, repo.as_ref(), setup_history_repository(&store), setup_job_repository(&store), &mut config);
+    // Changes made before a command failed are saved as well
+    repo.save()?;
+    response
+}
+
 fn main() {
-    let args = DumpBufferCLI::from_args();
-    match setup_repository().and_then(|repo| handle(&args, repo)) {
-        Ok(result) => println!("{}", result),
-        Err(e) => println!("[ERROR]: {}", e),
+    if env::args().nth(1).as_deref() == Some(COMPLETE_COMMAND) {
+        // Errors would be shown in the middle of the command line, so completion just offers nothing
+        if let Ok(response) = complete_keys() {
+            println!("{}", response.message);
+        }
+        return;
+    }
+    match run() {
+        Ok(response) => {
+            // Values read from files and stdin may end with a newline already
+            if response.message.ends_with('\n') {
+                print!("{}", response.message);
+            } else if !response.message.is_empty() {
+                println!("{}", response.message);
+            }
+            // Repositories are already dropped at this point, so their state is saved
+            if response.exit_code != 0 {
+                std::process::exit(response.exit_code);
+            }
+        }
+        Err(e) => {
+            eprintln!("[ERROR]: {}", e);
+            std::process::exit(e.exit_code());
+        }
     }
 }
@@ -1,23 +1,106 @@
 #![allow(dead_code)]
 
+use std::str::FromStr;
+
+use crate::record::domain::entities::{Record, RecordMetadata};
+use crate::record::domain::search::SearchPattern;
+
 pub struct AddNewRecordQuery {
     pub key: String,
     pub value: String,
+    pub metadata: RecordMetadata,
 }
 
 impl AddNewRecordQuery {
     pub fn new(key: String, value: String) -> AddNewRecordQuery {
-        return AddNewRecordQuery { key, value }
+        AddNewRecordQuery { key, value, metadata: RecordMetadata::default() }
+    }
+
+    pub fn with_metadata(self, metadata: RecordMetadata) -> AddNewRecordQuery {
+        AddNewRecordQuery { metadata, ..self }
+    }
+}
+
+pub struct AddWorkflowQuery {
+    pub key: String,
+    /// Keys of records executed one by one
+    pub steps: Vec<String>,
+}
+
+impl AddWorkflowQuery {
+    pub fn new(key: String, steps: Vec<String>) -> AddWorkflowQuery {
+        AddWorkflowQuery { key, steps }
+    }
+}
+
+pub struct SuggestRecordsQuery {
+    /// Commands from shell history, oldest first
+    pub commands: Vec<String>,
+    /// Shorter commands are not worth storing
+    pub min_length: usize,
+    /// Maximum number of suggestions
+    pub limit: usize,
+}
+
+impl SuggestRecordsQuery {
+    pub fn new(commands: Vec<String>, min_length: usize, limit: usize) -> SuggestRecordsQuery {
+        SuggestRecordsQuery { commands, min_length, limit }
+    }
+}
+
+/// What to do with imported record whose key is already used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictStrategy {
+    /// Keep existing record
+    Skip,
+    /// Replace existing record with imported one
+    Overwrite,
+    /// Store imported record under a new key
+    Rename,
+    /// Abort import without changing anything
+    Fail,
+}
+
+impl FromStr for ConflictStrategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "skip" => Ok(ConflictStrategy::Skip),
+            "overwrite" => Ok(ConflictStrategy::Overwrite),
+            "rename" => Ok(ConflictStrategy::Rename),
+            "fail" => Ok(ConflictStrategy::Fail),
+            _ => Err(format!("Conflict strategy \"{}\" is not supported", name)),
+        }
+    }
+}
+
+pub struct ImportRecordsQuery {
+    pub records: Vec<Record>,
+    pub on_conflict: ConflictStrategy,
+    /// Only report what would be done
+    pub dry_run: bool,
+}
+
+impl ImportRecordsQuery {
+    pub fn new(records: Vec<Record>, on_conflict: ConflictStrategy, dry_run: bool) -> ImportRecordsQuery {
+        ImportRecordsQuery { records, on_conflict, dry_run }
     }
 }
 
 pub struct GetRecordQuery {
     pub key: String,
+    /// Accept unambiguous prefix or fuzzy match of the key
+    pub fuzzy: bool,
 }
 
 impl GetRecordQuery {
     pub fn new(key: String) -> GetRecordQuery {
-        return  GetRecordQuery { key };
+        return  GetRecordQuery { key, fuzzy: false };
+    }
+
+    pub fn fuzzy(self, fuzzy: bool) -> GetRecordQuery {
+        GetRecordQuery { fuzzy, ..self }
     }
 }
 
@@ -31,6 +114,28 @@ impl ListRecordsQuery {
     }
 }
 
+pub struct UpdateRecordQuery {
+    pub key: String,
+    /// New value, metadata of the record is kept
+    pub value: String,
+}
+
+impl UpdateRecordQuery {
+    pub fn new(key: String, value: String) -> UpdateRecordQuery {
+        UpdateRecordQuery { key, value }
+    }
+}
+
+pub struct SearchRecordsQuery {
+    pub pattern: SearchPattern,
+}
+
+impl SearchRecordsQuery {
+    pub fn new(pattern: SearchPattern) -> SearchRecordsQuery {
+        SearchRecordsQuery { pattern }
+    }
+}
+
 #[derive(Debug)]
 pub struct DeleteRecordQuery {
     pub key: Option<String>,
@@ -1,7 +1,19 @@
 #![allow(dead_code)]
 
-use super::queries::{AddNewRecordQuery, DeleteRecordQuery, GetRecordQuery, ListRecordsQuery};
-use crate::record::domain::{entities::Record, repositories::RecordRepository};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use super::queries::{
+    AddNewRecordQuery, AddWorkflowQuery, ConflictStrategy, DeleteRecordQuery, GetRecordQuery,
+    ImportRecordsQuery, ListRecordsQuery, SearchRecordsQuery, SuggestRecordsQuery, UpdateRecordQuery,
+};
+use crate::record::domain::{
+    entities::{Record, RecordMetadata},
+    fuzzy::{not_found, resolve_key},
+    keys::{generate_key, unique_key},
+    repositories::RecordRepository,
+};
+use crate::shared::domain::error::Error;
 
 pub struct AddNewRecordService<'a> {
     record_repository: &'a dyn RecordRepository,
@@ -12,15 +24,165 @@ impl<'a> AddNewRecordService<'a> {
         return AddNewRecordService { record_repository };
     }
 
-    pub fn run(&self, query: &AddNewRecordQuery) -> Result<(), String> {
-        let record = Record {
-            key: query.key.to_string(),
-            value: query.value.to_string(),
-        };
+    pub fn run(&self, query: &AddNewRecordQuery) -> Result<(), Error> {
+        let record = Record::with_metadata(&query.key, &query.value, query.metadata.clone());
         self.record_repository.add(record)
     }
 }
 
+pub struct AddWorkflowService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> AddWorkflowService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> AddWorkflowService<'a> {
+        AddWorkflowService { record_repository }
+    }
+
+    pub fn run(&self, query: &AddWorkflowQuery) -> Result<(), Error> {
+        if query.steps.is_empty() {
+            return Err(Error::Invalid("Workflow should have at least one step".to_string()));
+        }
+        for step in &query.steps {
+            if step == &query.key {
+                return Err(Error::Invalid(format!("Workflow \"{}\" can not include itself", query.key)));
+            }
+            self.record_repository.get(step.to_string())?;
+        }
+        let metadata = RecordMetadata { steps: query.steps.clone(), ..RecordMetadata::default() };
+        let record = Record::with_metadata(&query.key, &query.steps.join(" -> "), metadata);
+        self.record_repository.add(record)
+    }
+}
+
+/// Command proposed to be stored as a record
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Generated key, not used by any record or other suggestion
+    pub key: String,
+    pub command: String,
+    /// Number of times command was used
+    pub count: usize,
+}
+
+pub struct SuggestRecordsService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> SuggestRecordsService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> SuggestRecordsService<'a> {
+        SuggestRecordsService { record_repository }
+    }
+
+    /// Rank commands by repetition, recently used first among equally used ones.
+    /// Commands which are already stored are skipped
+    pub fn run(&self, query: &SuggestRecordsQuery) -> Result<Vec<Suggestion>, Error> {
+        let records = self.record_repository.all()?;
+        let stored: HashSet<&str> = records.iter().map(|record| record.value.trim()).collect();
+        let mut taken: HashSet<String> = records.iter().map(|record| record.key.to_string()).collect();
+
+        // Number of uses and position of the last use of every command
+        let mut usage: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (position, command) in query.commands.iter().enumerate() {
+            let command = command.trim();
+            if command.chars().count() < query.min_length || stored.contains(command) {
+                continue;
+            }
+            let entry = usage.entry(command).or_insert((0, 0));
+            *entry = (entry.0 + 1, position);
+        }
+        let mut ranked: Vec<_> = usage.into_iter().collect();
+        ranked.sort_by_key(|(_, (count, last))| Reverse((*count, *last)));
+        Ok(ranked
+            .into_iter()
+            .take(query.limit)
+            .map(|(command, (count, _))| {
+                let key = generate_key(command, &taken);
+                taken.insert(key.to_string());
+                Suggestion { key, command: command.to_string(), count }
+            })
+            .collect())
+    }
+}
+
+pub struct ExportRecordsService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> ExportRecordsService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> ExportRecordsService<'a> {
+        ExportRecordsService { record_repository }
+    }
+
+    /// Get all records ordered by key, so that exports are stable
+    pub fn run(&self) -> Result<Vec<Record>, Error> {
+        let mut records = self.record_repository.all()?;
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(records)
+    }
+}
+
+/// What happened to every imported record
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    /// Original and new key of renamed records
+    pub renamed: Vec<(String, String)>,
+    pub skipped: Vec<String>,
+}
+
+pub struct ImportRecordsService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> ImportRecordsService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> ImportRecordsService<'a> {
+        ImportRecordsService { record_repository }
+    }
+
+    pub fn run(&self, query: &ImportRecordsQuery) -> Result<ImportSummary, Error> {
+        let mut taken: HashSet<String> =
+            self.record_repository.all()?.into_iter().map(|record| record.key).collect();
+        // Every decision is made before anything is changed, so that failed or dry run import changes nothing
+        let mut summary = ImportSummary::default();
+        let mut changes: Vec<(Record, bool)> = Vec::new();
+        for record in &query.records {
+            if !taken.contains(&record.key) {
+                taken.insert(record.key.to_string());
+                summary.added.push(record.key.to_string());
+                changes.push((record.clone(), false));
+                continue;
+            }
+            match query.on_conflict {
+                ConflictStrategy::Skip => summary.skipped.push(record.key.to_string()),
+                ConflictStrategy::Overwrite => {
+                    summary.overwritten.push(record.key.to_string());
+                    changes.push((record.clone(), true));
+                }
+                ConflictStrategy::Rename => {
+                    let key = unique_key(&record.key, &taken);
+                    taken.insert(key.to_string());
+                    summary.renamed.push((record.key.to_string(), key.to_string()));
+                    changes.push((Record { key, ..record.clone() }, false));
+                }
+                ConflictStrategy::Fail => return Err(Error::AlreadyExists(format!("Key \"{}\" already exists", record.key))),
+            }
+        }
+        if query.dry_run {
+            return Ok(summary);
+        }
+        for (record, existing) in changes {
+            if existing {
+                self.record_repository.update(record)?;
+            } else {
+                self.record_repository.add(record)?;
+            }
+        }
+        Ok(summary)
+    }
+}
+
 pub struct GetRecordService<'a> {
     record_repository: &'a dyn RecordRepository,
 }
@@ -30,8 +192,21 @@ impl<'a> GetRecordService<'a> {
         return GetRecordService { record_repository };
     }
 
-    pub fn run(&self, query: &GetRecordQuery) -> Result<Record, String> {
-        return self.record_repository.get(query.key.to_string());
+    /// Get record by key. Missing key is resolved fuzzily if asked, otherwise closest keys are suggested
+    pub fn run(&self, query: &GetRecordQuery) -> Result<Record, Error> {
+        let error = match self.record_repository.get(query.key.to_string()) {
+            Ok(record) => return Ok(record),
+            Err(error) => error,
+        };
+        let keys: Vec<String> = self.record_repository.all()?.into_iter().map(|record| record.key).collect();
+        if keys.contains(&query.key) {
+            return Err(error);
+        }
+        if query.fuzzy {
+            self.record_repository.get(resolve_key(&query.key, &keys)?)
+        } else {
+            Err(Error::NotFound(not_found(&query.key, &keys)))
+        }
     }
 }
 
@@ -49,7 +224,7 @@ impl<'a> ListRecordsService<'a> {
         return ListRecordsService { record_repository };
     }
 
-    pub fn run(&self, query: &ListRecordsQuery) -> Result<ListResult, String> {
+    pub fn run(&self, query: &ListRecordsQuery) -> Result<ListResult, Error> {
         self.record_repository.all().and_then(|records| {
             if query.keys_only {
                 let keys: Vec<String> = records.iter().map(|v| v.key.to_string()).collect();
@@ -61,6 +236,47 @@ impl<'a> ListRecordsService<'a> {
     }
 }
 
+pub struct UpdateRecordService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> UpdateRecordService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> UpdateRecordService<'a> {
+        UpdateRecordService { record_repository }
+    }
+
+    /// Replace value of existing record, returning the updated record
+    pub fn run(&self, query: &UpdateRecordQuery) -> Result<Record, Error> {
+        if query.value.trim().is_empty() {
+            return Err(Error::Invalid("Value can not be empty".to_string()));
+        }
+        let mut record = self.record_repository.get(query.key.to_string())?;
+        if record.metadata.is_workflow() {
+            return Err(Error::Invalid(format!("Workflow \"{}\" has no value to edit, it is made of steps", query.key)));
+        }
+        record.value = query.value.to_string();
+        self.record_repository.update(record.clone())?;
+        Ok(record)
+    }
+}
+
+pub struct SearchRecordsService<'a> {
+    record_repository: &'a dyn RecordRepository,
+}
+
+impl<'a> SearchRecordsService<'a> {
+    pub fn new(record_repository: &'a dyn RecordRepository) -> SearchRecordsService<'a> {
+        SearchRecordsService { record_repository }
+    }
+
+    /// Matching records ordered by key
+    pub fn run(&self, query: &SearchRecordsQuery) -> Result<Vec<Record>, Error> {
+        let mut records = self.record_repository.search(&query.pattern)?;
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(records)
+    }
+}
+
 pub struct ClearRecordsService <'a> {
     record_repository: &'a dyn RecordRepository,
 }
@@ -70,7 +286,7 @@ impl<'a> ClearRecordsService<'a> {
         return ClearRecordsService { record_repository };
     }
 
-    pub fn run(&self, query: &DeleteRecordQuery) -> Result<String, String> {
+    pub fn run(&self, query: &DeleteRecordQuery) -> Result<String, Error> {
         if query.all {
             self.record_repository
                 .clear()
@@ -80,7 +296,7 @@ impl<'a> ClearRecordsService<'a> {
                 .remove(key.to_string())
                 .and_then(|_| Ok(format!("Removed record with key \"{}\"", key)))
         } else {
-            Err(format!("Query should have at least one argument specified, got {:?}", query))
+            Err(Error::Invalid(format!("Query should have at least one argument specified, got {:?}", query)))
         }
     }
 }
@@ -88,6 +304,8 @@ impl<'a> ClearRecordsService<'a> {
 
 #[cfg(test)]
 mod test {
+    use crate::record::domain::entities::RecordMetadata;
+    use crate::record::domain::search::SearchPattern;
     use crate::record::infrastructure::repositories::InMemoryRecordRepository;
 
     use super::*;
@@ -105,4 +323,163 @@ mod test {
             Err(_) => assert!(false),
         }
     }
+
+    #[test]
+    fn add_workflow_stores_steps() {
+        let record_repository: InMemoryRecordRepository = InMemoryRecordRepository::new();
+        for key in ["build", "test"].iter() {
+            record_repository.add(Record::new(&key.to_string(), &"make".to_string())).unwrap();
+        }
+        let service = AddWorkflowService::new(&record_repository);
+        let steps = vec!["build".to_string(), "test".to_string()];
+        service.run(&AddWorkflowQuery::new("release".to_string(), steps.clone())).unwrap();
+
+        let record = record_repository.get("release".to_string()).unwrap();
+        assert_eq!(record.value, "build -> test");
+        assert_eq!(record.metadata.steps, steps);
+    }
+
+    #[test]
+    fn add_workflow_fails_for_unknown_or_recursive_steps() {
+        let record_repository: InMemoryRecordRepository = InMemoryRecordRepository::new();
+        let service = AddWorkflowService::new(&record_repository);
+        let unknown = AddWorkflowQuery::new("release".to_string(), vec!["missing".to_string()]);
+        let recursive = AddWorkflowQuery::new("release".to_string(), vec!["release".to_string()]);
+        assert!(service.run(&unknown).is_err());
+        assert!(service.run(&recursive).is_err());
+        assert!(record_repository.all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn add_new_record_keeps_metadata() {
+        let record_repository: InMemoryRecordRepository = InMemoryRecordRepository::new();
+        let mut metadata = RecordMetadata { cwd: Some("/tmp".to_string()), ..RecordMetadata::default() };
+        metadata.env.insert("AWS_PROFILE".to_string(), "dev".to_string());
+        let query = AddNewRecordQuery::new("key".to_string(), "aws s3 ls".to_string())
+            .with_metadata(metadata.clone());
+        AddNewRecordService::new(&record_repository).run(&query).unwrap();
+
+        assert_eq!(record_repository.get(query.key).unwrap().metadata, metadata);
+    }
+
+    #[test]
+    fn suggestions_are_ranked_by_repetition_and_skip_stored_commands() {
+        let record_repository: InMemoryRecordRepository = InMemoryRecordRepository::new();
+        record_repository.add(Record::new(&"git-log".to_string(), &"git log --oneline --graph".to_string())).unwrap();
+        let commands: Vec<String> = vec![
+            "docker compose up -d",
+            "ls",
+            "git log --oneline --graph",
+            "git log --oneline --stat",
+            "docker compose up -d",
+            "git log --oneline --stat",
+            "kubectl get pods -A",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        let service = SuggestRecordsService::new(&record_repository);
+        let suggestions = service.run(&SuggestRecordsQuery::new(commands, 10, 2)).unwrap();
+
+        assert_eq!(
+            suggestions,
+            vec![
+                Suggestion { key: "git-log-2".to_string(), command: "git log --oneline --stat".to_string(), count: 2 },
+                Suggestion { key: "docker-compose-up".to_string(), command: "docker compose up -d".to_string(), count: 2 },
+            ]
+        );
+    }
+
+    fn import_query(on_conflict: ConflictStrategy, dry_run: bool) -> ImportRecordsQuery {
+        let records = vec![
+            Record::new(&"new".to_string(), &"echo new".to_string()),
+            Record::new(&"old".to_string(), &"echo imported".to_string()),
+        ];
+        ImportRecordsQuery::new(records, on_conflict, dry_run)
+    }
+
+    fn repository_with_old_record() -> InMemoryRecordRepository {
+        let record_repository = InMemoryRecordRepository::new();
+        record_repository.add(Record::new(&"old".to_string(), &"echo old".to_string())).unwrap();
+        record_repository
+    }
+
+    #[test]
+    fn import_resolves_conflicts_with_chosen_strategy() {
+        let record_repository = repository_with_old_record();
+        let service = ImportRecordsService::new(&record_repository);
+        let summary = service.run(&import_query(ConflictStrategy::Rename, false)).unwrap();
+        assert_eq!(summary.added, vec!["new".to_string()]);
+        assert_eq!(summary.renamed, vec![("old".to_string(), "old-2".to_string())]);
+        assert_eq!(record_repository.get("old-2".to_string()).unwrap().value, "echo imported");
+
+        let record_repository = repository_with_old_record();
+        let service = ImportRecordsService::new(&record_repository);
+        let summary = service.run(&import_query(ConflictStrategy::Overwrite, false)).unwrap();
+        assert_eq!(summary.overwritten, vec!["old".to_string()]);
+        assert_eq!(record_repository.get("old".to_string()).unwrap().value, "echo imported");
+
+        let summary = service.run(&import_query(ConflictStrategy::Skip, false)).unwrap();
+        assert_eq!(summary.skipped, vec!["new".to_string(), "old".to_string()]);
+    }
+
+    #[test]
+    fn failed_or_dry_run_import_changes_nothing() {
+        let record_repository = repository_with_old_record();
+        let service = ImportRecordsService::new(&record_repository);
+        assert!(service.run(&import_query(ConflictStrategy::Fail, false)).is_err());
+        let summary = service.run(&import_query(ConflictStrategy::Overwrite, true)).unwrap();
+        assert_eq!(summary.added, vec!["new".to_string()]);
+        assert_eq!(record_repository.all().unwrap().len(), 1);
+        assert_eq!(record_repository.get("old".to_string()).unwrap().value, "echo old");
+    }
+
+    #[test]
+    fn search_matches_keys_values_and_descriptions() {
+        let record_repository = InMemoryRecordRepository::new();
+        let described = RecordMetadata { description: Some("Start Docker containers".to_string()), ..RecordMetadata::default() };
+        record_repository.add(Record::with_metadata("up", "compose up -d", described)).unwrap();
+        record_repository.add(Record::new(&"docker-ps".to_string(), &"docker ps\n--all".to_string())).unwrap();
+        record_repository.add(Record::new(&"ls".to_string(), &"ls -la".to_string())).unwrap();
+        let service = SearchRecordsService::new(&record_repository);
+
+        let query = SearchRecordsQuery::new(SearchPattern::new("docker", false, true).unwrap());
+        let keys: Vec<String> = service.run(&query).unwrap().into_iter().map(|record| record.key).collect();
+        assert_eq!(keys, vec!["docker-ps".to_string(), "up".to_string()]);
+
+        let query = SearchRecordsQuery::new(SearchPattern::new("^--all$", true, false).unwrap());
+        assert_eq!(service.run(&query).unwrap().len(), 1);
+        let query = SearchRecordsQuery::new(SearchPattern::new("^ps", true, false).unwrap());
+        assert!(service.run(&query).unwrap().is_empty());
+    }
+
+    #[test]
+    fn missing_key_is_resolved_fuzzily_or_closest_keys_are_suggested() {
+        let record_repository = InMemoryRecordRepository::new();
+        record_repository.add(Record::new(&"docker-compose-up".to_string(), &"docker compose up".to_string())).unwrap();
+        record_repository.add(Record::new(&"deploy".to_string(), &"./deploy.sh".to_string())).unwrap();
+        let service = GetRecordService::new(&record_repository);
+
+        assert_eq!(
+            service.run(&GetRecordQuery::new("deplyo".to_string())),
+            Err(Error::NotFound("Key \"deplyo\" does not exist. Did you mean \"deploy\"?".to_string()))
+        );
+        let record = service.run(&GetRecordQuery::new("dcup".to_string()).fuzzy(true)).unwrap();
+        assert_eq!(record.key, "docker-compose-up");
+        assert!(service.run(&GetRecordQuery::new("kubectl".to_string()).fuzzy(true)).is_err());
+    }
+
+    #[test]
+    fn update_replaces_value_and_keeps_metadata() {
+        let record_repository = InMemoryRecordRepository::new();
+        let metadata = RecordMetadata { cwd: Some("/srv".to_string()), ..RecordMetadata::default() };
+        record_repository.add(Record::with_metadata("up", "docker compose up", metadata.clone())).unwrap();
+        let service = UpdateRecordService::new(&record_repository);
+
+        let record = service.run(&UpdateRecordQuery::new("up".to_string(), "docker compose up -d".to_string())).unwrap();
+        assert_eq!(record, Record::with_metadata("up", "docker compose up -d", metadata));
+        assert_eq!(record_repository.get("up".to_string()).unwrap(), record);
+        assert!(service.run(&UpdateRecordQuery::new("up".to_string(), " \n".to_string())).is_err());
+        assert!(service.run(&UpdateRecordQuery::new("missing".to_string(), "ls".to_string())).is_err());
+    }
 }
@@ -1,14 +1,67 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+/// Additional optional information attached to a record
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct RecordMetadata {
+    /// Human readable explanation of what record does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Labels used to group records
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Directory to execute record in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Environment variables set for execution of record
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Default execution timeout in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Default number of retries after failed execution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// Default pause between retries in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_delay_ms: Option<u64>,
+    /// Program executing the value written into a script file, e.g. "python3"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    /// Keys of records executed one by one when record is a workflow
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<String>,
+    /// Captured output of the last execution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_output: Option<String>,
+}
+
+impl RecordMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == RecordMetadata::default()
+    }
+
+    pub fn is_workflow(&self) -> bool {
+        !self.steps.is_empty()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct Record {
     pub key: String,
     pub value: String,
+    pub metadata: RecordMetadata,
 }
 
 impl Record {
     pub fn new(key: &String, value: &String) -> Record {
-        Record { key: key.to_string(), value: value.to_string() }
+        Record { key: key.to_string(), value: value.to_string(), metadata: RecordMetadata::default() }
+    }
+
+    pub fn with_metadata(key: &str, value: &str, metadata: RecordMetadata) -> Record {
+        Record { key: key.to_string(), value: value.to_string(), metadata }
     }
 }
 
@@ -0,0 +1,174 @@
+use crate::shared::domain::error::Error;
+
+/// Maximum number of keys suggested instead of a missing one
+const MAX_SUGGESTIONS: usize = 3;
+/// Bonus for a matched character following the previous matched one
+const CONSECUTIVE_BONUS: i64 = 5;
+/// Bonus for a matched character starting a word
+const WORD_START_BONUS: i64 = 3;
+/// Maximum penalty for characters skipped before the first match
+const MAX_LEADING_PENALTY: i64 = 10;
+/// Shorter queries are a typo away from too many keys, e.g. "rn" from "rm", to be resolved by a typo
+const MIN_TYPO_QUERY_LENGTH: usize = 4;
+
+/// Number of single character insertions, deletions and substitutions turning one text into another
+pub fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Whether all characters of `query` appear in `key` in the same order, e.g. "dcu" in "docker-compose-up"
+pub fn is_subsequence(query: &str, key: &str) -> bool {
+    let mut chars = key.chars();
+    query.chars().all(|wanted| chars.any(|c| c == wanted))
+}
+
+/// How well `query` matches `text` ignoring case, `None` if text does not contain its characters in order.
+/// Consecutive characters and characters starting words score more, e.g. "dcu" in "docker compose up",
+/// matches closer to the start of the text are preferred
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    let mut first_match = None;
+    for (position, c) in text.chars().enumerate() {
+        let next = match wanted.peek() {
+            Some(next) => *next,
+            None => break,
+        };
+        let matched = c.to_lowercase().eq(std::iter::once(next));
+        if matched {
+            first_match.get_or_insert(position);
+            score += 1;
+            if previous_matched {
+                score += CONSECUTIVE_BONUS;
+            }
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += WORD_START_BONUS;
+            }
+            wanted.next();
+        }
+        previous_matched = matched;
+        previous = Some(c);
+    }
+    match wanted.peek() {
+        Some(_) => None,
+        None => Some(score - (first_match.unwrap_or(0) as i64).min(MAX_LEADING_PENALTY)),
+    }
+}
+
+/// Typos of up to a third of the key length are tolerated
+fn is_close(query: &str, key: &str) -> bool {
+    distance(query, key) <= (query.chars().count() / 3).max(1)
+}
+
+/// Keys most similar to the missing one, the closest first
+pub fn closest_keys(query: &str, keys: &[String]) -> Vec<String> {
+    let mut candidates: Vec<(usize, &String)> = keys
+        .iter()
+        .filter(|key| is_close(query, key) || key.starts_with(query) || is_subsequence(query, key))
+        .map(|key| (distance(query, key), key))
+        .collect();
+    candidates.sort();
+    candidates.into_iter().take(MAX_SUGGESTIONS).map(|(_, key)| key.to_string()).collect()
+}
+
+/// Find key meant by `query`: exact key, the only key starting with it, the only key containing
+/// its characters in order, or the only key differing by a typo if the query is long enough.
+/// Ambiguous queries are errors listing candidates
+pub fn resolve_key(query: &str, keys: &[String]) -> Result<String, Error> {
+    if keys.iter().any(|key| key == query) {
+        return Ok(query.to_string());
+    }
+    let typo_allowed = query.chars().count() >= MIN_TYPO_QUERY_LENGTH;
+    let matchers: [&dyn Fn(&str) -> bool; 3] = [
+        &|key| key.starts_with(query),
+        &|key| is_subsequence(query, key),
+        &|key| typo_allowed && is_close(query, key),
+    ];
+    for matcher in matchers.iter() {
+        let mut found: Vec<&String> = keys.iter().filter(|key| matcher(key)).collect();
+        match found.len() {
+            0 => continue,
+            1 => return Ok(found[0].to_string()),
+            _ => {
+                found.sort();
+                let found: Vec<String> = found.iter().map(|key| format!("\"{}\"", key)).collect();
+                return Err(Error::Invalid(format!("Key \"{}\" is ambiguous, it may be {}", query, found.join(", "))));
+            }
+        }
+    }
+    Err(Error::NotFound(not_found(query, keys)))
+}
+
+/// Error about missing key, suggesting the closest existing ones
+pub fn not_found(query: &str, keys: &[String]) -> String {
+    let suggestions: Vec<String> = closest_keys(query, keys).iter().map(|key| format!("\"{}\"", key)).collect();
+    match suggestions.len() {
+        0 => format!("Key \"{}\" does not exist", query),
+        _ => format!("Key \"{}\" does not exist. Did you mean {}?", query, suggestions.join(" or ")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keys() -> Vec<String> {
+        vec!["docker-compose-up", "docker-compose-down", "deploy", "git-log"].into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn distance_counts_edits() {
+        assert_eq!(distance("deploy", "deploy"), 0);
+        assert_eq!(distance("depoly", "deploy"), 2);
+        assert_eq!(distance("gitlog", "git-log"), 1);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn closest_keys_are_suggested() {
+        assert_eq!(closest_keys("deplyo", &keys()), vec!["deploy".to_string()]);
+        assert_eq!(closest_keys("docker", &keys()), vec!["docker-compose-up".to_string(), "docker-compose-down".to_string()]);
+        assert!(closest_keys("kubectl", &keys()).is_empty());
+        assert_eq!(not_found("gitlog", &keys()), "Key \"gitlog\" does not exist. Did you mean \"git-log\"?");
+    }
+
+    #[test]
+    fn score_prefers_consecutive_characters_and_word_starts() {
+        assert_eq!(score("", "anything"), Some(0));
+        assert_eq!(score("xyz", "docker"), None);
+        assert!(score("dcu", "docker compose up") > score("dcu", "dockerscups"));
+        assert!(score("DOCK", "docker") > score("dock", "da-oc-k"));
+        assert!(score("db", "db:x echo db") > score("db", "both greet -> db:x"));
+    }
+
+    #[test]
+    fn unambiguous_prefix_subsequence_or_typo_is_resolved() {
+        assert_eq!(resolve_key("dep", &keys()), Ok("deploy".to_string()));
+        assert_eq!(resolve_key("dcup", &keys()), Ok("docker-compose-up".to_string()));
+        assert_eq!(resolve_key("git-lgo", &keys()), Ok("git-log".to_string()));
+        assert_eq!(
+            resolve_key("docker", &keys()),
+            Err(Error::Invalid("Key \"docker\" is ambiguous, it may be \"docker-compose-down\", \"docker-compose-up\"".to_string()))
+        );
+        assert!(resolve_key("kubectl", &keys()).is_err());
+    }
+
+    #[test]
+    fn short_query_is_not_resolved_by_typo() {
+        let keys: Vec<String> = vec!["rm".to_string(), "stop".to_string()];
+        assert!(matches!(resolve_key("rn", &keys), Err(Error::NotFound(message)) if message.contains("\"rm\"")));
+        assert_eq!(resolve_key("stap", &keys), Ok("stop".to_string()));
+    }
+}
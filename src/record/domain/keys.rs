@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+/// Maximum number of command words used in a generated key
+const KEY_WORDS: usize = 3;
+
+/// Generate readable key from the first words of a command, e.g. "docker-compose-up".
+/// Options, environment assignments and `sudo` are skipped
+pub fn generate_key(command: &str, taken: &HashSet<String>) -> String {
+    let words: Vec<String> = command
+        .split_whitespace()
+        .filter(|word| !word.starts_with('-') && !word.contains('=') && *word != "sudo")
+        .map(|word| {
+            let name = word.rsplit('/').next().unwrap_or(word);
+            let sanitized: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c.to_ascii_lowercase() } else { '-' })
+                .collect();
+            sanitized.trim_matches('-').to_string()
+        })
+        .filter(|word| !word.is_empty())
+        .take(KEY_WORDS)
+        .collect();
+    let base = if words.is_empty() { "command".to_string() } else { words.join("-") };
+    unique_key(&base, taken)
+}
+
+/// Add numeric suffix to `base` if it is taken, e.g. "deploy-2"
+pub fn unique_key(base: &str, taken: &HashSet<String>) -> String {
+    let mut key = base.to_string();
+    let mut number = 1;
+    while taken.contains(&key) {
+        number += 1;
+        key = format!("{}-{}", base, number);
+    }
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn key_is_built_from_first_command_words() {
+        let taken = HashSet::new();
+        assert_eq!(generate_key("docker compose up -d --build", &taken), "docker-compose-up");
+        assert_eq!(generate_key("sudo RUST_LOG=debug ./scripts/deploy.sh prod", &taken), "deploy-sh-prod");
+        assert_eq!(generate_key("-- && ||", &taken), "command");
+    }
+
+    #[test]
+    fn taken_keys_get_numeric_suffix() {
+        let taken: HashSet<String> = vec!["git-log".to_string(), "git-log-2".to_string()].into_iter().collect();
+        assert_eq!(generate_key("git log --oneline", &taken), "git-log-3");
+    }
+}
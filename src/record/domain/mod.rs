@@ -1,2 +1,6 @@
 pub mod entities;
-pub mod repositories;
\ No newline at end of file
+pub mod fuzzy;
+pub mod keys;
+pub mod repositories;
+pub mod scripts;
+pub mod search;
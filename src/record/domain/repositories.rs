@@ -1,36 +1,61 @@
 use crate::record::domain::entities::Record;
+use crate::record::domain::search::SearchPattern;
+use crate::shared::domain::error::Error;
 
 pub trait RecordRepository {
     /// Add new record to the data storage
-    fn add(&self, record: Record) -> Result<(), String>;
+    fn add(&self, record: Record) -> Result<(), Error>;
     /// Get record by key
-    fn get(&self, key: String) -> Result<Record, String>;
+    fn get(&self, key: String) -> Result<Record, Error>;
+    /// Replace value and metadata of existing record
+    fn update(&self, record: Record) -> Result<(), Error>;
     /// Remove record by key
-    fn remove(&self, key: String) -> Result<(), String>;
+    fn remove(&self, key: String) -> Result<(), Error>;
     /// Get all records from the data storage
-    fn all(&self) -> Result<Vec<Record>, String>;
+    fn all(&self) -> Result<Vec<Record>, Error>;
     /// Clear all records from storage
-    fn clear(&self) -> Result<(), String>;
+    fn clear(&self) -> Result<(), Error>;
+    /// Records whose key, value or description matches the pattern.
+    /// Storages able to search by themselves should override it instead of reading every record
+    fn search(&self, pattern: &SearchPattern) -> Result<Vec<Record>, Error> {
+        Ok(self.all()?.into_iter().filter(|record| pattern.matches(record)).collect())
+    }
+    /// Write changes kept in memory to the data storage, storages writing every change at once have nothing to do
+    fn save(&self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 impl<T> RecordRepository for Box<T> where T: RecordRepository + ?Sized {
-    fn add(&self, record: Record) -> Result<(), String> {
+    fn add(&self, record: Record) -> Result<(), Error> {
         (**self).add(record)
     }
 
-    fn get(&self, key: String) -> Result<Record, String> {
+    fn get(&self, key: String) -> Result<Record, Error> {
         (**self).get(key)
     }
 
-    fn remove(&self, key: String) -> Result<(), String> {
+    fn update(&self, record: Record) -> Result<(), Error> {
+        (**self).update(record)
+    }
+
+    fn remove(&self, key: String) -> Result<(), Error> {
         (**self).remove(key)
     }
 
-    fn all(&self) -> Result<Vec<Record>, String> {
+    fn all(&self) -> Result<Vec<Record>, Error> {
         (**self).all()
     }
 
-    fn clear(&self) -> Result<(), String> {
+    fn clear(&self) -> Result<(), Error> {
         (**self).clear()
     }
+
+    fn search(&self, pattern: &SearchPattern) -> Result<Vec<Record>, Error> {
+        (**self).search(pattern)
+    }
+
+    fn save(&self) -> Result<(), Error> {
+        (**self).save()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use std::path::Path;
+
+/// Interpreters guessed from extension of an imported script file
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("sh", "sh"),
+    ("bash", "bash"),
+    ("zsh", "zsh"),
+    ("fish", "fish"),
+    ("py", "python3"),
+    ("js", "node"),
+    ("rb", "ruby"),
+    ("pl", "perl"),
+];
+
+/// Whether value starts with a shebang line and can be executed as a script on its own
+pub fn has_shebang(value: &str) -> bool {
+    value.starts_with("#!")
+}
+
+/// Guess interpreter of a script file. Scripts with a shebang need no interpreter
+pub fn guess_interpreter(path: &Path, content: &str) -> Option<String> {
+    if has_shebang(content) {
+        return None;
+    }
+    let extension = path.extension()?.to_str()?;
+    INTERPRETERS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        .map(|(_, interpreter)| interpreter.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interpreter_is_guessed_from_extension() {
+        assert_eq!(guess_interpreter(Path::new("build.py"), "print(1)"), Some("python3".to_string()));
+        assert_eq!(guess_interpreter(Path::new("dir/run.SH"), "echo 1"), Some("sh".to_string()));
+        assert_eq!(guess_interpreter(Path::new("notes.txt"), "echo 1"), None);
+        assert_eq!(guess_interpreter(Path::new("Makefile"), "echo 1"), None);
+    }
+
+    #[test]
+    fn shebang_takes_precedence_over_extension() {
+        assert_eq!(guess_interpreter(Path::new("run.py"), "#!/usr/bin/env python2\nprint 1"), None);
+    }
+}
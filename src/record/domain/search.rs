@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::record::domain::entities::Record;
+use crate::shared::domain::error::Error;
+
+/// Text looked for in keys, values and descriptions of records.
+/// Options are kept next to the compiled regex, so that storages can build their own query from them
+#[derive(Debug, Clone)]
+pub struct SearchPattern {
+    text: String,
+    is_regex: bool,
+    ignore_case: bool,
+    regex: Regex,
+}
+
+impl SearchPattern {
+    /// Pattern matching `text` as a substring, or as a regular expression if `is_regex` is set.
+    /// `^` and `$` match at line boundaries, like grep does for multi-line values
+    pub fn new(text: &str, is_regex: bool, ignore_case: bool) -> Result<SearchPattern, Error> {
+        if text.is_empty() {
+            return Err(Error::Invalid("Search pattern is empty".to_string()));
+        }
+        let source = if is_regex { text.to_string() } else { regex::escape(text) };
+        RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .multi_line(true)
+            .build()
+            .map(|regex| SearchPattern { text: text.to_string(), is_regex, ignore_case, regex })
+            .map_err(|e| Error::Parse(format!("Invalid regular expression: {}", e)))
+    }
+
+    /// Text as it was given, a regular expression only if `is_regex` is set
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_regex(&self) -> bool {
+        self.is_regex
+    }
+
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Byte ranges of all non-empty matches in the text
+    pub fn find(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex.find_iter(text).map(|found| found.range()).filter(|range| !range.is_empty()).collect()
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        !self.find(text).is_empty()
+    }
+
+    /// Whether key, value or description of the record matches
+    pub fn matches(&self, record: &Record) -> bool {
+        self.is_match(&record.key)
+            || self.is_match(&record.value)
+            || record.metadata.description.as_deref().is_some_and(|description| self.is_match(description))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn substring_is_not_treated_as_regex() {
+        let pattern = SearchPattern::new("a.b", false, false).unwrap();
+        assert_eq!(pattern.find("a.b axb a.b"), vec![0..3, 8..11]);
+        assert_eq!((pattern.text(), pattern.is_regex(), pattern.ignore_case()), ("a.b", false, false));
+    }
+
+    #[test]
+    fn regex_can_ignore_case() {
+        let pattern = SearchPattern::new("^docker (up|down)", true, true).unwrap();
+        assert!(pattern.is_match("Docker UP -d"));
+        assert_eq!((pattern.text(), pattern.is_regex(), pattern.ignore_case()), ("^docker (up|down)", true, true));
+        assert!(!SearchPattern::new("^docker (up|down)", true, false).unwrap().is_match("Docker UP -d"));
+        assert!(SearchPattern::new("(", true, false).is_err());
+        assert!(SearchPattern::new("", false, false).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::record::domain::entities::Record;
+use crate::record::domain::scripts::has_shebang;
+
+/// Characters separating namespace of a key from the rest, e.g. "db:migrate" or "docker/up"
+const NAMESPACE_SEPARATORS: &[char] = &[':', '/', '.'];
+/// Interpreters which have a different name of the language in fenced code blocks
+const LANGUAGES: &[(&str, &str)] = &[("node", "javascript"), ("python3", "python"), ("python2", "python")];
+
+/// How records are split into sections of a cheat sheet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    /// Section per tag, record with several tags is put into each of them
+    Tag,
+    /// Section per key prefix before the first separator
+    Namespace,
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "tag" => Ok(Grouping::Tag),
+            "namespace" => Ok(Grouping::Namespace),
+            _ => Err(format!("Grouping \"{}\" is not supported", name)),
+        }
+    }
+}
+
+/// Names of sections the record is put into, records without one are put into "Other" section
+fn sections(record: &Record, grouping: Grouping) -> Vec<String> {
+    match grouping {
+        Grouping::Tag => record.metadata.tags.clone(),
+        Grouping::Namespace => record
+            .key
+            .split_once(NAMESPACE_SEPARATORS)
+            .map(|(namespace, _)| namespace.to_string())
+            .filter(|namespace| !namespace.is_empty())
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Inline code, delimited by more backticks than any sequence inside of it
+fn code(text: &str) -> String {
+    let fence = "`".repeat(longest_backticks(text) + 1);
+    let padding = if text.starts_with('`') || text.ends_with('`') { " " } else { "" };
+    format!("{}{}{}{}{}", fence, padding, text, padding, fence)
+}
+
+fn longest_backticks(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Name of the program in a command line, skipping `env` with its flags and variables,
+/// e.g. "python3" for "/usr/bin/env -S python3 -u"
+fn program(command: &str) -> &str {
+    let mut words = command.split_whitespace().map(|word| word.rsplit('/').next().unwrap_or(word));
+    let first = words.next().unwrap_or_default();
+    if first != "env" {
+        return first;
+    }
+    words.find(|word| !word.starts_with('-') && !word.contains('=')).unwrap_or(first)
+}
+
+/// Language of fenced code block, taken from interpreter or shebang of a script
+fn language(record: &Record) -> String {
+    let program = match &record.metadata.interpreter {
+        Some(interpreter) => program(interpreter),
+        None if has_shebang(&record.value) => {
+            program(record.value.lines().next().unwrap_or_default().trim_start_matches("#!"))
+        }
+        None => "sh",
+    };
+    LANGUAGES
+        .iter()
+        .find(|(known, _)| *known == program)
+        .map_or(program, |(_, language)| language)
+        .to_string()
+}
+
+fn write_record(lines: &mut Vec<String>, record: &Record) {
+    let metadata = &record.metadata;
+    lines.push(format!("### {}", code(&record.key)));
+    lines.push(String::new());
+    if let Some(description) = &metadata.description {
+        lines.push(description.to_string());
+        lines.push(String::new());
+    }
+    let mut details = Vec::new();
+    if let Some(cwd) = &metadata.cwd {
+        details.push(format!("- Directory: {}", code(cwd)));
+    }
+    if !metadata.env.is_empty() {
+        let env: Vec<String> = metadata.env.iter().map(|(name, value)| code(&format!("{}={}", name, value))).collect();
+        details.push(format!("- Environment: {}", env.join(", ")));
+    }
+    if !details.is_empty() {
+        lines.extend(details);
+        lines.push(String::new());
+    }
+    if metadata.is_workflow() {
+        let steps: Vec<String> = metadata.steps.iter().map(|step| code(step)).collect();
+        lines.push(format!("Workflow: {}", steps.join(" → ")));
+    } else {
+        let fence = "`".repeat(longest_backticks(&record.value).max(2) + 1);
+        lines.push(format!("{}{}", fence, language(record)));
+        lines.push(record.value.trim_end_matches('\n').to_string());
+        lines.push(fence);
+    }
+    lines.push(String::new());
+}
+
+/// Markdown document with a section per group and a code block per record, records keep their order
+pub fn cheat_sheet(records: &[Record], grouping: Grouping) -> String {
+    let mut groups: BTreeMap<String, Vec<&Record>> = BTreeMap::new();
+    let mut other: Vec<&Record> = Vec::new();
+    for record in records {
+        let sections = sections(record, grouping);
+        if sections.is_empty() {
+            other.push(record);
+        }
+        for section in sections {
+            groups.entry(section).or_default().push(record);
+        }
+    }
+    let mut lines = vec!["# Records".to_string(), String::new()];
+    let other = if other.is_empty() { None } else { Some(("Other".to_string(), other)) };
+    for (section, records) in groups.into_iter().chain(other) {
+        lines.push(format!("## {}", section));
+        lines.push(String::new());
+        for record in records {
+            write_record(&mut lines, record);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::record::domain::entities::RecordMetadata;
+
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        let tagged = |key: &str, value: &str, tags: &[&str]| {
+            let metadata = RecordMetadata { tags: tags.iter().map(|tag| tag.to_string()).collect(), ..RecordMetadata::default() };
+            Record::with_metadata(key, value, metadata)
+        };
+        vec![
+            tagged("db:migrate", "diesel migration run", &["db", "dev"]),
+            tagged("docker:up", "docker compose up", &["dev"]),
+            tagged("hello", "echo hello", &[]),
+        ]
+    }
+
+    #[test]
+    fn records_are_grouped_by_tags_with_untagged_ones_last() {
+        let markdown = cheat_sheet(&records(), Grouping::Tag);
+        let headings: Vec<&str> = markdown.lines().filter(|line| line.starts_with('#')).collect();
+        assert_eq!(
+            headings,
+            vec!["# Records", "## db", "### `db:migrate`", "## dev", "### `db:migrate`", "### `docker:up`", "## Other", "### `hello`"]
+        );
+    }
+
+    #[test]
+    fn records_are_grouped_by_key_namespace() {
+        let markdown = cheat_sheet(&records(), Grouping::Namespace);
+        let headings: Vec<&str> = markdown.lines().filter(|line| line.starts_with("## ")).collect();
+        assert_eq!(headings, vec!["## db", "## docker", "## Other"]);
+    }
+
+    #[test]
+    fn record_is_written_with_description_details_and_fenced_code() {
+        let mut metadata = RecordMetadata {
+            description: Some("Print greeting".to_string()),
+            cwd: Some("/srv".to_string()),
+            interpreter: Some("python3 -u".to_string()),
+            ..RecordMetadata::default()
+        };
+        metadata.env.insert("NAME".to_string(), "world".to_string());
+        let record = Record::with_metadata("greet", "print(f\"```{1}\")\n", metadata);
+        assert_eq!(
+            cheat_sheet(&[record], Grouping::Tag),
+            "# Records\n\n## Other\n\n### `greet`\n\nPrint greeting\n\n- Directory: `/srv`\n- Environment: `NAME=world`\n\n\
+             ````python\nprint(f\"```{1}\")\n````\n"
+        );
+    }
+
+    #[test]
+    fn language_is_named_after_program_of_shebang() {
+        let language = |value: &str| language(&Record::new(&"k".to_string(), &value.to_string()));
+        assert_eq!(language("#!/bin/bash -e\necho"), "bash");
+        assert_eq!(language("#!/usr/bin/env -S python3 -u\nprint()"), "python");
+        assert_eq!(language("#! /usr/bin/env LC_ALL=C node\n1"), "javascript");
+        assert_eq!(language("echo"), "sh");
+    }
+
+    #[test]
+    fn workflow_lists_its_steps() {
+        let metadata = RecordMetadata { steps: vec!["build".to_string(), "test".to_string()], ..RecordMetadata::default() };
+        let markdown = cheat_sheet(&[Record::with_metadata("ci", "build -> test", metadata)], Grouping::Tag);
+        assert!(markdown.contains("Workflow: `build` → `test`\n"));
+    }
+}
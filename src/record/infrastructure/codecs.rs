@@ -0,0 +1,182 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::record::domain::entities::{Record, RecordMetadata};
+use crate::shared::domain::error::Error;
+
+/// Format of exported records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Yaml,
+    Csv,
+    /// Cheat sheet for people to read, it can not be imported
+    Markdown,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "yml" => Some(Format::Yaml),
+            "md" => Some(Format::Markdown),
+            other => other.parse().ok(),
+        }
+    }
+
+    /// Detect format by file extension, falling back to file content
+    pub fn detect(path: &Path, content: &str) -> Format {
+        if let Some(format) = Format::from_path(path) {
+            return format;
+        }
+        let content = content.trim_start();
+        if content.starts_with('[') || content.starts_with('{') {
+            Format::Json
+        } else if content.starts_with("key,value") {
+            Format::Csv
+        } else {
+            Format::Yaml
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "json" => Ok(Format::Json),
+            "yaml" => Ok(Format::Yaml),
+            "csv" => Ok(Format::Csv),
+            "markdown" => Ok(Format::Markdown),
+            _ => Err(format!("Format \"{}\" is not supported", name)),
+        }
+    }
+}
+
+/// Record as written into JSON and YAML, metadata fields are put next to key and value
+#[derive(Serialize, Deserialize)]
+struct ExportedRecord {
+    key: String,
+    value: String,
+    #[serde(flatten)]
+    metadata: RecordMetadata,
+}
+
+/// Record as written into CSV, which has no nested values, so metadata is kept as JSON
+#[derive(Serialize, Deserialize)]
+struct CsvRecord {
+    key: String,
+    value: String,
+    #[serde(default)]
+    metadata: String,
+}
+
+impl From<&Record> for ExportedRecord {
+    fn from(record: &Record) -> Self {
+        ExportedRecord { key: record.key.to_string(), value: record.value.to_string(), metadata: record.metadata.clone() }
+    }
+}
+
+pub fn encode(records: &[Record], format: Format) -> Result<String, Error> {
+    let exported: Vec<ExportedRecord> = records.iter().map(ExportedRecord::from).collect();
+    let error = |e: &dyn ToString| Error::Storage(format!("Could not encode records: {}", e.to_string()));
+    match format {
+        Format::Json => serde_json::to_string_pretty(&exported).map_err(|e| error(&e)),
+        Format::Yaml => serde_yaml::to_string(&exported).map_err(|e| error(&e)),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for record in exported {
+                let metadata = if record.metadata.is_empty() {
+                    String::new()
+                } else {
+                    serde_json::to_string(&record.metadata).map_err(|e| error(&e))?
+                };
+                writer
+                    .serialize(CsvRecord { key: record.key, value: record.value, metadata })
+                    .map_err(|e| error(&e))?;
+            }
+            let content = writer.into_inner().map_err(|e| error(&e))?;
+            String::from_utf8(content).map_err(|e| error(&e))
+        }
+        Format::Markdown => Err(Error::Invalid("Markdown cheat sheet can not be encoded as records".to_string())),
+    }
+}
+
+/// Single record as an object instead of a list, only JSON and YAML can hold it
+pub fn encode_record(record: &Record, format: Format) -> Result<String, Error> {
+    let error = |e: &dyn ToString| Error::Storage(format!("Could not encode record: {}", e.to_string()));
+    match format {
+        Format::Json => serde_json::to_string_pretty(&ExportedRecord::from(record)).map_err(|e| error(&e)),
+        Format::Yaml => serde_yaml::to_string(&ExportedRecord::from(record)).map_err(|e| error(&e)),
+        _ => Err(Error::Invalid(format!("Single record can not be written as {:?}", format))),
+    }
+}
+
+pub fn decode(content: &str, format: Format) -> Result<Vec<Record>, Error> {
+    let error = |e: &dyn ToString| Error::Parse(format!("Could not parse records: {}", e.to_string()));
+    let exported: Vec<ExportedRecord> = match format {
+        Format::Json => serde_json::from_str(content).map_err(|e| error(&e))?,
+        Format::Yaml => serde_yaml::from_str(content).map_err(|e| error(&e))?,
+        Format::Csv => csv::Reader::from_reader(content.as_bytes())
+            .deserialize()
+            .map(|row| {
+                let row: CsvRecord = row.map_err(|e| error(&e))?;
+                let metadata = match row.metadata.trim() {
+                    "" => RecordMetadata::default(),
+                    raw => serde_json::from_str(raw).map_err(|e| error(&e))?,
+                };
+                Ok(ExportedRecord { key: row.key, value: row.value, metadata })
+            })
+            .collect::<Result<_, Error>>()?,
+        Format::Markdown => return Err(Error::Invalid("Markdown cheat sheet can not be imported".to_string())),
+    };
+    Ok(exported
+        .into_iter()
+        .map(|record| Record::with_metadata(&record.key, &record.value, record.metadata))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        let metadata = RecordMetadata {
+            tags: vec!["dev".to_string()],
+            cwd: Some("/srv/app".to_string()),
+            ..RecordMetadata::default()
+        };
+        vec![
+            Record::with_metadata("up", "docker compose up -d", metadata),
+            Record::new(&"quoted".to_string(), &"echo \"a, b\"\nsecond line".to_string()),
+        ]
+    }
+
+    #[test]
+    fn records_survive_round_trip_in_every_format() {
+        for format in &[Format::Json, Format::Yaml, Format::Csv] {
+            let encoded = encode(&records(), *format).unwrap();
+            assert_eq!(decode(&encoded, *format).unwrap(), records(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn metadata_is_flattened_into_json() {
+        let encoded = encode(&records()[..1], Format::Json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(parsed[0]["tags"][0], "dev");
+        assert_eq!(parsed[0]["cwd"], "/srv/app");
+    }
+
+    #[test]
+    fn format_is_detected_by_extension_or_content() {
+        assert_eq!(Format::detect(Path::new("backup.yml"), "[]"), Format::Yaml);
+        assert_eq!(Format::detect(Path::new("backup"), "  [{\"key\": \"a\"}]"), Format::Json);
+        assert_eq!(Format::detect(Path::new("backup"), "key,value,metadata\n"), Format::Csv);
+        assert_eq!(Format::detect(Path::new("backup"), "- key: a\n  value: b\n"), Format::Yaml);
+        assert_eq!(Format::detect(Path::new("CHEATSHEET.md"), "# Records"), Format::Markdown);
+    }
+}
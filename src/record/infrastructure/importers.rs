@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::record::domain::entities::{Record, RecordMetadata};
+use crate::record::domain::keys::generate_key;
+use crate::shared::domain::error::Error;
+
+/// Snippet managers and files records can be converted from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    /// navi `.cheat` files
+    Navi,
+    /// pet `snippet.toml`
+    Pet,
+    /// `alias name='command'` lines of shell rc files
+    Aliases,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "navi" => Ok(Source::Navi),
+            "pet" => Ok(Source::Pet),
+            "aliases" => Ok(Source::Aliases),
+            _ => Err(format!("Source \"{}\" is not supported", name)),
+        }
+    }
+}
+
+/// Item of a source file which could not be converted into a record
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unconverted {
+    pub item: String,
+    pub reason: String,
+}
+
+impl Display for Unconverted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\": {}", self.item, self.reason)
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Conversion {
+    pub records: Vec<Record>,
+    pub unconverted: Vec<Unconverted>,
+}
+
+impl Conversion {
+    fn unconverted(&mut self, item: &str, reason: &str) {
+        self.unconverted.push(Unconverted { item: item.to_string(), reason: reason.to_string() });
+    }
+}
+
+pub fn convert(content: &str, source: Source) -> Result<Conversion, Error> {
+    match source {
+        Source::Navi => Ok(convert_navi(content)),
+        Source::Pet => convert_pet(content),
+        Source::Aliases => Ok(convert_aliases(content)),
+    }
+}
+
+/// Snippet with key generated from description, or from command if there is no description
+fn snippet(command: &str, description: Option<String>, tags: Vec<String>, taken: &mut HashSet<String>) -> Record {
+    let key = generate_key(description.as_deref().unwrap_or(command), taken);
+    taken.insert(key.to_string());
+    let metadata = RecordMetadata { description, tags, ..RecordMetadata::default() };
+    Record::with_metadata(&key, command, metadata)
+}
+
+/// navi cheatsheet: "% tags" line applies to following snippets, "# description" line precedes command lines.
+/// Placeholders like `<branch>` are kept, records use the same syntax.
+/// Variable definitions ("$"), comments (";") and extensions ("@") are not snippets
+fn convert_navi(content: &str) -> Conversion {
+    let mut conversion = Conversion::default();
+    let mut taken = HashSet::new();
+    let (mut tags, mut description, mut command): (Vec<String>, Option<String>, Vec<&str>) = (vec![], None, vec![]);
+    let mut flush = |description: &mut Option<String>, command: &mut Vec<&str>, tags: &Vec<String>| {
+        if command.is_empty() {
+            return;
+        }
+        let value = command.join("\n");
+        conversion.records.push(snippet(&value, description.take(), tags.clone(), &mut taken));
+        command.clear();
+        *description = None;
+    };
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(raw_tags) = trimmed.strip_prefix('%') {
+            flush(&mut description, &mut command, &tags);
+            tags = raw_tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(String::from).collect();
+        } else if let Some(raw_description) = trimmed.strip_prefix('#') {
+            flush(&mut description, &mut command, &tags);
+            description = Some(raw_description.trim().to_string());
+        } else if trimmed.is_empty() || trimmed.starts_with(&['$', ';', '@'][..]) {
+            flush(&mut description, &mut command, &tags);
+        } else {
+            command.push(line);
+        }
+    }
+    flush(&mut description, &mut command, &tags);
+    conversion
+}
+
+#[derive(Deserialize)]
+struct PetFile {
+    #[serde(default)]
+    snippets: Vec<PetSnippet>,
+}
+
+#[derive(Deserialize)]
+struct PetSnippet {
+    #[serde(default)]
+    description: String,
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+}
+
+/// pet snippets, parameters with default values (`<name=default>`) are replaced with the defaults,
+/// parameters without one (`<name>`) are kept as placeholders of records
+fn convert_pet(content: &str) -> Result<Conversion, Error> {
+    let file: PetFile = toml::from_str(content).map_err(|e| Error::Parse(format!("Could not parse pet snippets: {}", e)))?;
+    let with_default = Regex::new(r"<[A-Za-z_][A-Za-z0-9_-]*=([^<>|]*)>").unwrap();
+    let mut conversion = Conversion::default();
+    let mut taken = HashSet::new();
+    for pet in file.snippets {
+        if pet.command.contains("|_") {
+            conversion.unconverted(&pet.command, "uses parameters with choices (|_value_|), which are not supported");
+            continue;
+        }
+        let command = with_default.replace_all(&pet.command, "$1");
+        let description = Some(pet.description).filter(|description| !description.is_empty());
+        conversion.records.push(snippet(&command, description, pet.tag, &mut taken));
+    }
+    Ok(conversion)
+}
+
+/// Aliases in bash/zsh (`alias name='command'`) and fish (`alias name 'command'`) syntax,
+/// other lines of rc files are ignored
+fn convert_aliases(content: &str) -> Conversion {
+    let mut conversion = Conversion::default();
+    for line in content.lines().map(str::trim) {
+        let definition = match line.strip_prefix("alias ") {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let words = match shlex::split(definition) {
+            Some(words) => words,
+            None => {
+                conversion.unconverted(line, "could not be parsed");
+                continue;
+            }
+        };
+        if words.iter().any(|word| word.starts_with('-')) {
+            conversion.unconverted(line, "alias options are not supported");
+            continue;
+        }
+        let aliases: Vec<(String, String)> = match words.as_slice() {
+            [name, command] if !name.contains('=') => vec![(name.to_string(), command.to_string())],
+            _ => words
+                .iter()
+                .filter_map(|word| word.split_once('='))
+                .map(|(name, command)| (name.to_string(), command.to_string()))
+                .collect(),
+        };
+        for (name, command) in aliases {
+            if name.is_empty() || command.trim().is_empty() {
+                conversion.unconverted(line, "alias has no name or command");
+                continue;
+            }
+            // Later definition overrides earlier one, the same way it does in shell
+            conversion.records.retain(|record| record.key != name);
+            conversion.records.push(Record::new(&name, &command));
+        }
+    }
+    conversion
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn navi_snippets_keep_descriptions_and_tags() {
+        let content = "% git, code\n\n# Show last commits\ngit log -n 5 \\\n  --oneline\n\n# Change branch\ngit checkout <branch>\n\n$ branch: git branch\n; comment\n";
+        let conversion = convert_navi(content);
+
+        assert_eq!(conversion.records.len(), 2);
+        let record = &conversion.records[0];
+        assert_eq!(record.key, "show-last-commits");
+        assert_eq!(record.value, "git log -n 5 \\\n  --oneline");
+        assert_eq!(record.metadata.description, Some("Show last commits".to_string()));
+        assert_eq!(record.metadata.tags, vec!["git".to_string(), "code".to_string()]);
+        assert_eq!(conversion.records[1].key, "change-branch");
+        assert_eq!(conversion.records[1].value, "git checkout <branch>");
+        assert!(conversion.unconverted.is_empty());
+    }
+
+    #[test]
+    fn pet_parameters_are_replaced_with_defaults() {
+        let content = r#"
+[[snippets]]
+  description = "Ping host"
+  command = "ping -c <count=3> example.com"
+  tag = ["network"]
+  output = ""
+
+[[snippets]]
+  description = "Ssh"
+  command = "ssh <host>"
+
+[[snippets]]
+  description = "Log level"
+  command = "run --level <level=|_info_||_debug_|>"
+"#;
+        let conversion = convert_pet(content).unwrap();
+
+        assert_eq!(conversion.records.len(), 2);
+        assert_eq!(conversion.records[0].key, "ping-host");
+        assert_eq!(conversion.records[0].value, "ping -c 3 example.com");
+        assert_eq!(conversion.records[0].metadata.tags, vec!["network".to_string()]);
+        assert_eq!(conversion.records[1].value, "ssh <host>");
+        assert_eq!(conversion.unconverted.len(), 1);
+        assert_eq!(conversion.unconverted[0].item, "run --level <level=|_info_||_debug_|>");
+        assert!(convert_pet("snippets = 1").is_err());
+    }
+
+    #[test]
+    fn aliases_are_read_in_every_shell_syntax() {
+        let content = "export PATH=$PATH:~/bin\nalias ll='ls -la'\nalias gs=\"git status\"\nalias ll 'ls -lah'\nalias -g G='| grep'\nalias broken='oops\n";
+        let conversion = convert_aliases(content);
+
+        let aliases: Vec<(&str, &str)> =
+            conversion.records.iter().map(|record| (record.key.as_str(), record.value.as_str())).collect();
+        assert_eq!(aliases, vec![("gs", "git status"), ("ll", "ls -lah")]);
+        assert_eq!(conversion.unconverted.len(), 2);
+    }
+}
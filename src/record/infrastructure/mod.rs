@@ -1 +1,7 @@
-pub mod repositories;
\ No newline at end of file
+pub mod cheat_sheet;
+pub mod codecs;
+pub mod importers;
+pub mod output;
+pub mod repositories;
+pub mod shell_script;
+pub mod template;
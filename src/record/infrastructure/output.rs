@@ -0,0 +1,282 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::record::domain::entities::Record;
+use crate::record::infrastructure::codecs::{encode, encode_record, Format};
+use crate::shared::domain::error::Error;
+
+/// Narrowest a column gets when table is truncated to the terminal width
+const MIN_COLUMN_WIDTH: usize = 5;
+/// Space between columns of a table
+const COLUMN_GAP: &str = "  ";
+
+/// Format of records printed by `list`, `get` and `show`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Yaml,
+    /// Aligned columns for people to read
+    Table,
+    /// Text as printed without any format, e.g. only the value for `get`
+    Plain,
+    /// Tab separated values with a header, tabs and newlines in values are escaped
+    Tsv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            "table" => Ok(OutputFormat::Table),
+            "plain" => Ok(OutputFormat::Plain),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!("Output format \"{}\" is not supported", name)),
+        }
+    }
+}
+
+/// Fields of record shown by `show`, only the ones which are set
+fn fields(record: &Record) -> Vec<(&'static str, String)> {
+    let metadata = &record.metadata;
+    let mut fields = vec![("key", record.key.to_string()), ("value", record.value.to_string())];
+    let duration = |ms: u64| humantime::format_duration(Duration::from_millis(ms)).to_string();
+    fields.extend(metadata.description.clone().map(|description| ("description", description)));
+    if !metadata.tags.is_empty() {
+        fields.push(("tags", metadata.tags.join(", ")));
+    }
+    fields.extend(metadata.cwd.clone().map(|cwd| ("cwd", cwd)));
+    if !metadata.env.is_empty() {
+        let env: Vec<String> = metadata.env.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        fields.push(("env", env.join("\n")));
+    }
+    fields.extend(metadata.interpreter.clone().map(|interpreter| ("interpreter", interpreter)));
+    if metadata.is_workflow() {
+        fields.push(("steps", metadata.steps.join(", ")));
+    }
+    fields.extend(metadata.timeout_ms.map(|timeout| ("timeout", duration(timeout))));
+    fields.extend(metadata.retries.map(|retries| ("retries", retries.to_string())));
+    fields.extend(metadata.retry_delay_ms.map(|delay| ("retry delay", duration(delay))));
+    fields.extend(metadata.last_output.clone().map(|output| ("last output", output)));
+    fields
+}
+
+/// Record together with its metadata, one field per line
+pub fn details(record: &Record) -> String {
+    let mut lines = Vec::new();
+    for (name, value) in fields(record) {
+        if name == "env" {
+            lines.push("env:".to_string());
+            lines.extend(value.lines().map(|variable| format!("  {}", variable)));
+        } else {
+            lines.push(format!("{}: {}", name, value));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Rows of text with named columns, written as a table or as tab separated values
+struct Table {
+    header: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Columns of listed records, only the ones which are set for some record
+    fn of_records(records: &[Record]) -> Table {
+        let mut header = vec!["key", "value"];
+        let has_descriptions = records.iter().any(|record| record.metadata.description.is_some());
+        let has_tags = records.iter().any(|record| !record.metadata.tags.is_empty());
+        if has_descriptions {
+            header.push("description");
+        }
+        if has_tags {
+            header.push("tags");
+        }
+        let rows = records
+            .iter()
+            .map(|record| {
+                let mut row = vec![record.key.to_string(), record.value.to_string()];
+                if has_descriptions {
+                    row.push(record.metadata.description.clone().unwrap_or_default());
+                }
+                if has_tags {
+                    row.push(record.metadata.tags.join(","));
+                }
+                row
+            })
+            .collect();
+        Table { header, rows }
+    }
+
+    /// Tabs and newlines are escaped, so that every record takes exactly one line
+    fn tsv(&self) -> String {
+        let escape = |cell: &str| cell.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r");
+        let mut lines = vec![self.header.join("\t")];
+        lines.extend(self.rows.iter().map(|row| row.iter().map(|cell| escape(cell)).collect::<Vec<_>>().join("\t")));
+        lines.join("\n")
+    }
+
+    /// Aligned columns, the widest ones are shortened to fit into `width` characters
+    fn table(&self, width: Option<usize>) -> String {
+        let flatten = |cell: &str| cell.trim_end().replace('\t', " ").replace('\n', " ↵ ");
+        let header: Vec<String> = self.header.iter().map(|name| name.to_uppercase()).collect();
+        let rows: Vec<Vec<String>> =
+            self.rows.iter().map(|row| row.iter().map(|cell| flatten(cell)).collect()).collect();
+        let mut widths: Vec<usize> = header.iter().map(|name| name.chars().count()).collect();
+        for row in &rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(cell.chars().count());
+            }
+        }
+        if let Some(width) = width {
+            let gaps = COLUMN_GAP.len() * (widths.len() - 1);
+            while widths.iter().sum::<usize>() + gaps > width {
+                let (widest, _) = widths.iter().enumerate().max_by_key(|(_, width)| **width).unwrap();
+                if widths[widest] <= MIN_COLUMN_WIDTH {
+                    break;
+                }
+                widths[widest] -= 1;
+            }
+        }
+        let fit = |cell: &str, width: usize, last: bool| -> String {
+            let cell = if cell.chars().count() > width {
+                let shortened: String = cell.chars().take(width - 1).collect();
+                format!("{}…", shortened)
+            } else {
+                cell.to_string()
+            };
+            // Last column is not padded, so that lines have no trailing spaces
+            if last {
+                cell
+            } else {
+                format!("{:<width$}", cell, width = width)
+            }
+        };
+        std::iter::once(&header)
+            .chain(rows.iter())
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .enumerate()
+                    .map(|(column, cell)| fit(cell, widths[column], column == row.len() - 1))
+                    .collect();
+                cells.join(COLUMN_GAP).trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Output of `list`, table is truncated to `width` if it is given
+pub fn list_output(records: &[Record], format: OutputFormat, width: Option<usize>) -> Result<String, Error> {
+    match format {
+        OutputFormat::Json => encode(records, Format::Json),
+        OutputFormat::Yaml => encode(records, Format::Yaml),
+        OutputFormat::Table => Ok(Table::of_records(records).table(width)),
+        OutputFormat::Tsv => Ok(Table::of_records(records).tsv()),
+        OutputFormat::Plain => {
+            let mut lines = Vec::new();
+            for record in records {
+                lines.push(record.key.to_string());
+                lines.extend(record.value.lines().map(|line| format!("    {}", line)));
+            }
+            Ok(lines.join("\n"))
+        }
+    }
+}
+
+/// Output of `list --keys-only`
+pub fn keys_output(keys: &[String], format: OutputFormat, width: Option<usize>) -> Result<String, Error> {
+    let table = Table { header: vec!["key"], rows: keys.iter().map(|key| vec![key.to_string()]).collect() };
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(keys).map_err(|e| Error::Storage(e.to_string())),
+        OutputFormat::Yaml => serde_yaml::to_string(keys).map_err(|e| Error::Storage(e.to_string())),
+        OutputFormat::Table => Ok(table.table(width)),
+        OutputFormat::Tsv => Ok(table.tsv()),
+        OutputFormat::Plain => Ok(keys.join("\n")),
+    }
+}
+
+/// Output of `get`, plain format is the value alone
+pub fn value_output(record: &Record, format: OutputFormat, width: Option<usize>) -> Result<String, Error> {
+    match format {
+        OutputFormat::Plain => Ok(record.value.to_string()),
+        _ => record_output(record, format, width),
+    }
+}
+
+/// Output of `show`, where table and tab separated values have a row per field
+pub fn record_output(record: &Record, format: OutputFormat, width: Option<usize>) -> Result<String, Error> {
+    let table = || Table {
+        header: vec!["field", "value"],
+        rows: fields(record).into_iter().map(|(name, value)| vec![name.to_string(), value]).collect(),
+    };
+    match format {
+        OutputFormat::Json => encode_record(record, Format::Json),
+        OutputFormat::Yaml => encode_record(record, Format::Yaml),
+        OutputFormat::Table => Ok(table().table(width)),
+        OutputFormat::Tsv => Ok(table().tsv()),
+        OutputFormat::Plain => Ok(details(record)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::record::domain::entities::RecordMetadata;
+
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        let metadata = RecordMetadata { description: Some("Start services".to_string()), ..RecordMetadata::default() };
+        vec![
+            Record::with_metadata("up", "docker compose up --detach --remove-orphans", metadata),
+            Record::new(&"tabs".to_string(), &"printf 'a\\tb'\necho \\".to_string()),
+        ]
+    }
+
+    #[test]
+    fn json_is_valid_and_has_stable_field_names() {
+        let parsed: serde_json::Value = serde_json::from_str(&list_output(&records(), OutputFormat::Json, None).unwrap()).unwrap();
+        assert_eq!(parsed[0]["key"], "up");
+        assert_eq!(parsed[0]["description"], "Start services");
+        assert_eq!(parsed[1]["value"], "printf 'a\\tb'\necho \\");
+        let parsed: serde_json::Value = serde_json::from_str(&value_output(&records()[0], OutputFormat::Json, None).unwrap()).unwrap();
+        assert_eq!(parsed["key"], "up");
+        assert_eq!(keys_output(&["a\"b".to_string()], OutputFormat::Json, None).unwrap(), "[\n  \"a\\\"b\"\n]");
+    }
+
+    #[test]
+    fn tsv_escapes_tabs_and_newlines() {
+        assert_eq!(
+            list_output(&records(), OutputFormat::Tsv, None).unwrap(),
+            "key\tvalue\tdescription\n\
+             up\tdocker compose up --detach --remove-orphans\tStart services\n\
+             tabs\tprintf 'a\\\\tb'\\necho \\\\\t"
+        );
+    }
+
+    #[test]
+    fn table_is_truncated_to_width() {
+        let table = list_output(&records(), OutputFormat::Table, Some(40)).unwrap();
+        assert_eq!(
+            table,
+            "KEY   VALUE               DESCRIPTION\n\
+             up    docker compose up…  Start services\n\
+             tabs  printf 'a\\tb' ↵ e…"
+        );
+        assert!(table.lines().all(|line| line.chars().count() <= 40));
+        assert!(list_output(&records(), OutputFormat::Table, None).unwrap().contains("--remove-orphans  Start services"));
+    }
+
+    #[test]
+    fn show_has_row_per_field() {
+        assert_eq!(
+            record_output(&records()[0], OutputFormat::Tsv, None).unwrap(),
+            "field\tvalue\nkey\tup\nvalue\tdocker compose up --detach --remove-orphans\ndescription\tStart services"
+        );
+        assert_eq!(value_output(&records()[1], OutputFormat::Plain, None).unwrap(), "printf 'a\\tb'\necho \\");
+    }
+}
@@ -3,14 +3,15 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-use crate::record::domain::entities::Record;
+use crate::record::domain::entities::{Record, RecordMetadata};
 use crate::record::domain::repositories::RecordRepository;
+use crate::shared::domain::error::Error;
 use crate::shared::infrastructure::file_db::KVFileDatabase;
 
 /// Repository to store records in memory. Primary use case - testing
 pub struct InMemoryRecordRepository {
     /// HashMap-based key-value storage for records
-    storage: RefCell<HashMap<String, String>>,
+    storage: RefCell<HashMap<String, Record>>,
 }
 
 impl InMemoryRecordRepository {
@@ -22,36 +23,46 @@ impl InMemoryRecordRepository {
 }
 
 impl RecordRepository for InMemoryRecordRepository {
-    fn add(&self, record: Record) -> Result<(), String> {
-        let (key, value) = (record.key.to_string(), record.value.to_string());
-        match self.storage.borrow_mut().insert(key.to_string(), value) {
-            Some(_) => Err(format!("Value for key \"{}\" was updated", key)),
+    fn add(&self, record: Record) -> Result<(), Error> {
+        let key = record.key.to_string();
+        match self.storage.borrow_mut().insert(key.to_string(), record) {
+            Some(_) => Err(Error::AlreadyExists(format!("Value for key \"{}\" was updated", key))),
             None => Ok(())
         }
     }
 
-    fn get(&self, key: String) -> Result<Record, String> {
-        match self.storage.borrow().get(&key).clone() {
-            Some(v) => Ok(Record { key, value: v.to_string() }),
-            None => Err(format!("Record with key \"{}\" does not exist", key))
+    fn get(&self, key: String) -> Result<Record, Error> {
+        match self.storage.borrow().get(&key) {
+            Some(record) => Ok(record.clone()),
+            None => Err(Error::NotFound(format!("Record with key \"{}\" does not exist", key)))
         }
     }
 
-    fn remove(&self, key: String) -> Result<(), String> {
+    fn update(&self, record: Record) -> Result<(), Error> {
+        let mut storage = self.storage.borrow_mut();
+        match storage.get_mut(&record.key) {
+            Some(existing) => {
+                *existing = record;
+                Ok(())
+            }
+            None => Err(Error::NotFound(format!("Record with key \"{}\" does not exist", record.key))),
+        }
+    }
+
+    fn remove(&self, key: String) -> Result<(), Error> {
         match self.storage.borrow_mut().remove(&key) {
             Some(_) => Ok(()),
-            None => Err("Record could not be removed".to_string()),
+            None => Err(Error::NotFound("Record could not be removed".to_string())),
         }
     }
 
-    fn all(&self) -> Result<Vec<Record>, String> {
-        Ok(self.storage.borrow().iter()
-            .map(|(k, v)| Record::new(k, v))
-            .collect())
+    fn all(&self) -> Result<Vec<Record>, Error> {
+        Ok(self.storage.borrow().values().cloned().collect())
     }
 
-    fn clear(&self) -> Result<(), String> {
-        Ok(self.storage.borrow_mut().clear())
+    fn clear(&self) -> Result<(), Error> {
+        self.storage.borrow_mut().clear();
+        Ok(())
     }
 }
 
@@ -60,34 +71,77 @@ impl RecordRepository for InMemoryRecordRepository {
 pub struct KVFileDatabaseRepository {
     /// Underlying file storage implementation
     storage: KVFileDatabase,
+    /// Separate file storage for serialized record metadata
+    metadata: KVFileDatabase,
 }
 
 impl KVFileDatabaseRepository {
-    pub fn new(storage: KVFileDatabase) -> Self {
-        KVFileDatabaseRepository { storage }
+    pub fn new(storage: KVFileDatabase, metadata: KVFileDatabase) -> Self {
+        KVFileDatabaseRepository { storage, metadata }
+    }
+
+    fn read_metadata(&self, key: &String) -> Result<RecordMetadata, Error> {
+        if !self.metadata.contains(key) {
+            return Ok(RecordMetadata::default());
+        }
+        let raw = self.metadata.get(key)?;
+        serde_json::from_str(&raw)
+            .map_err(|e| Error::Storage(format!("Metadata of key \"{}\" has incompatible format: {}", key, e)))
+    }
+
+    fn write_metadata(&self, key: &String, metadata: &RecordMetadata) -> Result<(), Error> {
+        if metadata.is_empty() {
+            if self.metadata.contains(key) {
+                self.metadata.remove(key)?;
+            }
+            return Ok(());
+        }
+        let raw = serde_json::to_string(metadata).map_err(|e| Error::Storage(e.to_string()))?;
+        self.metadata.set(key, &raw)
     }
 }
 
 impl RecordRepository for KVFileDatabaseRepository {
-    fn add(&self, record: Record) -> Result<(), String> {
-        self.storage.add(&record.key, &record.value)
+    fn add(&self, record: Record) -> Result<(), Error> {
+        self.storage.add(&record.key, &record.value)?;
+        self.write_metadata(&record.key, &record.metadata)
+    }
+
+    fn get(&self, key: String) -> Result<Record, Error> {
+        let value = self.storage.get(&key)?;
+        Ok(Record::with_metadata(&key, &value, self.read_metadata(&key)?))
     }
 
-    fn get(&self, key: String) -> Result<Record, String> {
-        self.storage.get(&key).and_then(|v| Ok(Record::new(&key, &v)))
+    fn update(&self, record: Record) -> Result<(), Error> {
+        if !self.storage.contains(&record.key) {
+            return Err(Error::NotFound(format!("Key \"{}\" does not exist", record.key)));
+        }
+        self.storage.set(&record.key, &record.value)?;
+        self.write_metadata(&record.key, &record.metadata)
     }
 
-    fn remove(&self, key: String) -> Result<(), String> {
-        self.storage.remove(&key)
+    fn remove(&self, key: String) -> Result<(), Error> {
+        self.storage.remove(&key)?;
+        self.write_metadata(&key, &RecordMetadata::default())
     }
 
-    fn all(&self) -> Result<Vec<Record>, String> {
-        self.storage.items().and_then(|items| -> Result<Vec<Record>, String> {
-            Ok(items.iter().map(|(k, v)| Record::new(&k, &v)).collect())
+    fn all(&self) -> Result<Vec<Record>, Error> {
+        self.storage.items().and_then(|items| -> Result<Vec<Record>, Error> {
+            items
+                .iter()
+                .map(|(k, v)| Ok(Record::with_metadata(k, v, self.read_metadata(k)?)))
+                .collect()
         })
     }
 
-    fn clear(&self) -> Result<(), String> {
-        Ok(self.storage.clear())
+    fn clear(&self) -> Result<(), Error> {
+        self.storage.clear();
+        self.metadata.clear();
+        Ok(())
     }
-}
\ No newline at end of file
+
+    fn save(&self) -> Result<(), Error> {
+        self.storage.save()?;
+        self.metadata.save()
+    }
+}
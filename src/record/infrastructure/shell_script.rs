@@ -0,0 +1,409 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::record::domain::entities::Record;
+use crate::record::domain::keys::unique_key;
+use crate::record::domain::scripts::has_shebang;
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::shell_history::Shell;
+
+/// Quote text so that shell passes it as a single word without any expansion
+fn quote(shell: Shell, text: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", text.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+/// Reference to a positional argument of a function, numbered from 1
+fn argument(shell: Shell, number: usize) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("\"${{{}}}\"", number),
+        Shell::Fish => format!("$argv[{}]", number),
+    }
+}
+
+/// Reference to arguments of a function starting with the given one
+fn arguments_from(shell: Shell, number: usize) -> String {
+    match (shell, number) {
+        (Shell::Bash, 1) | (Shell::Zsh, 1) => "\"$@\"".to_string(),
+        (Shell::Bash, _) | (Shell::Zsh, _) => format!("\"${{@:{}}}\"", number),
+        (Shell::Fish, 1) => "$argv".to_string(),
+        (Shell::Fish, _) => format!("$argv[{}..-1]", number),
+    }
+}
+
+/// Turn key into a valid function name, e.g. "db:migrate" becomes "db_migrate"
+fn function_name(key: &str, taken: &HashSet<String>) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    unique_key(&name, taken)
+}
+
+/// Placeholder like `<branch>` in a command, filled in when the record is used
+fn placeholder() -> Regex {
+    Regex::new(r"<[A-Za-z_][A-Za-z0-9_-]*>").unwrap()
+}
+
+/// Words of a command line run with record environment, e.g. `command env 'A=1' 'ls' '-la' "$@"`.
+/// Records are executed without shell, so every word is quoted. Placeholders like `<branch>`
+/// are replaced with positional arguments in order of appearance, the rest of arguments is appended
+fn command_line(shell: Shell, record: &Record, words: &[String]) -> String {
+    let placeholder = placeholder();
+    let mut placeholders: Vec<&str> = Vec::new();
+    let mut line: Vec<String> = Vec::new();
+    // Function may have the same name as the program it runs, so functions are not looked up
+    if record.metadata.env.is_empty() {
+        line.push("command".to_string());
+    } else {
+        line.push("command env".to_string());
+        line.extend(record.metadata.env.iter().map(|(name, value)| quote(shell, &format!("{}={}", name, value))));
+    }
+    for word in words {
+        let mut quoted = String::new();
+        let mut last = 0;
+        for found in placeholder.find_iter(word) {
+            if found.start() > last {
+                quoted.push_str(&quote(shell, &word[last..found.start()]));
+            }
+            let number = match placeholders.iter().position(|name| *name == found.as_str()) {
+                Some(index) => index + 1,
+                None => {
+                    placeholders.push(found.as_str());
+                    placeholders.len()
+                }
+            };
+            quoted.push_str(&argument(shell, number));
+            last = found.end();
+        }
+        if last < word.len() || word.is_empty() {
+            quoted.push_str(&quote(shell, &word[last..]));
+        }
+        line.push(quoted);
+    }
+    line.push(arguments_from(shell, placeholders.len() + 1));
+    line.join(" ")
+}
+
+/// Run command line in record directory without changing directory of the calling shell.
+/// Cleanup runs after the command, which exit status is kept
+fn run_lines(shell: Shell, record: &Record, line: String, cleanup: Option<&str>) -> Vec<String> {
+    let cwd = record.metadata.cwd.as_ref().map(|cwd| quote(shell, cwd));
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            let run = match cwd {
+                Some(cwd) => format!("( cd -- {} || exit; {} )", cwd, line),
+                None => line,
+            };
+            match cleanup {
+                Some(cleanup) => vec![run, "code=$?".to_string(), cleanup.to_string(), "return $code".to_string()],
+                None => vec![run],
+            }
+        }
+        Shell::Fish => {
+            if cwd.is_none() && cleanup.is_none() {
+                return vec![line];
+            }
+            let mut lines = Vec::new();
+            if let Some(cwd) = &cwd {
+                lines.push("set -l previous $PWD".to_string());
+                match cleanup {
+                    Some(cleanup) => lines.push(format!("cd {}; or begin; {}; return 1; end", cwd, cleanup)),
+                    None => lines.push(format!("cd {}; or return", cwd)),
+                }
+            }
+            lines.push(line);
+            lines.push("set -l code $status".to_string());
+            if cwd.is_some() {
+                lines.push("cd $previous".to_string());
+            }
+            lines.extend(cleanup.map(str::to_string));
+            lines.push("return $code".to_string());
+            lines
+        }
+    }
+}
+
+/// Scripts are written into a temporary file, which is removed after execution
+fn script_body(shell: Shell, record: &Record) -> Result<Vec<String>, String> {
+    let mut words = match &record.metadata.interpreter {
+        Some(interpreter) => shlex::split(interpreter).ok_or("could not split interpreter")?,
+        None => Vec::new(),
+    };
+    // Script path is a variable, so it is put into command line unquoted
+    let placeholder = "\u{0}script\u{0}";
+    words.push(placeholder.to_string());
+    let script = match shell {
+        Shell::Bash | Shell::Zsh => "\"$script\"",
+        Shell::Fish => "$script",
+    };
+    let run = command_line(shell, record, &words).replace(&quote(shell, placeholder), script);
+    let mut lines = match shell {
+        Shell::Bash | Shell::Zsh => vec!["local script code".to_string(), "script=$(mktemp) || return".to_string()],
+        Shell::Fish => vec!["set -l script (mktemp); or return".to_string()],
+    };
+    lines.push(format!("printf '%s' {} > {}", quote(shell, &record.value), script));
+    lines.push(format!("chmod +x {}", script));
+    lines.extend(run_lines(shell, record, run, Some(&format!("rm -f {}", script))));
+    Ok(lines)
+}
+
+fn function_body(shell: Shell, record: &Record, names: &HashMap<String, String>) -> Result<Vec<String>, String> {
+    if record.metadata.is_workflow() {
+        let steps: Vec<String> = record
+            .metadata
+            .steps
+            .iter()
+            .map(|step| names.get(step).cloned().ok_or(format!("step \"{}\" does not exist", step)))
+            .collect::<Result<_, _>>()?;
+        let separator = match shell {
+            Shell::Bash | Shell::Zsh => " && ",
+            Shell::Fish => "; and ",
+        };
+        return Ok(vec![steps.join(separator)]);
+    }
+    if record.metadata.interpreter.is_some() || has_shebang(&record.value) {
+        return script_body(shell, record);
+    }
+    let words = shlex::split(&record.value).ok_or("could not split command")?;
+    if words.is_empty() {
+        return Err("command is empty".to_string());
+    }
+    Ok(run_lines(shell, record, command_line(shell, record, &words), None))
+}
+
+/// Script defining a function per record, which can be sourced by the shell.
+/// Functions forward their arguments to the command, records which can not be converted are commented
+pub fn export_script(records: &[Record], shell: Shell) -> String {
+    let mut taken = HashSet::new();
+    let mut names = HashMap::new();
+    for record in records {
+        let name = function_name(&record.key, &taken);
+        taken.insert(name.to_string());
+        names.insert(record.key.to_string(), name);
+    }
+    let shell_name = format!("{:?}", shell).to_lowercase();
+    let mut lines = vec![format!("# Records of dumpb, generated by `dumpb export --as {}`", shell_name)];
+    for record in records {
+        let name = &names[&record.key];
+        lines.push(String::new());
+        let body = match function_body(shell, record, &names) {
+            Ok(body) => body,
+            Err(e) => {
+                lines.push(format!("# Record \"{}\" is skipped: {}", record.key, e));
+                continue;
+            }
+        };
+        if let Some(description) = &record.metadata.description {
+            lines.extend(description.lines().map(|line| format!("# {}", line)));
+        }
+        match shell {
+            Shell::Bash | Shell::Zsh => lines.push(format!("{}() {{", name)),
+            Shell::Fish => lines.push(format!("function {}", name)),
+        }
+        lines.extend(body.iter().map(|line| format!("    {}", line)));
+        match shell {
+            Shell::Bash | Shell::Zsh => lines.push("}".to_string()),
+            Shell::Fish => lines.push("end".to_string()),
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Command line put into the prompt by shell widgets, so it can be edited and is saved in shell history.
+/// Values of placeholders are asked for with `value_of` once per name and quoted, as shell would take `<name>`
+/// for redirections. Workflows and scripts do not fit into a line, so they are executed by dumpb
+pub fn prompt_line(
+    shell: Shell,
+    record: &Record,
+    value_of: &mut dyn FnMut(&str) -> Result<String, Error>,
+) -> Result<String, Error> {
+    if record.metadata.is_workflow()
+        || record.metadata.interpreter.is_some()
+        || has_shebang(&record.value)
+        || record.value.trim_end().contains('\n')
+    {
+        return Ok(format!("dumpb exec -- {}", quote(shell, &record.key)));
+    }
+    let mut line = String::new();
+    if let Some(cwd) = &record.metadata.cwd {
+        let separator = match shell {
+            Shell::Bash | Shell::Zsh => " &&",
+            Shell::Fish => "; and",
+        };
+        line.push_str(&format!("cd -- {}{} ", quote(shell, cwd), separator));
+    }
+    if !record.metadata.env.is_empty() {
+        line.push_str("env ");
+        for (name, value) in &record.metadata.env {
+            line.push_str(&format!("{} ", quote(shell, &format!("{}={}", name, value))));
+        }
+    }
+    let command = record.value.trim_end();
+    let mut values: HashMap<&str, String> = HashMap::new();
+    let mut last = 0;
+    for found in placeholder().find_iter(command) {
+        line.push_str(&command[last..found.start()]);
+        let name = found.as_str();
+        if !values.contains_key(name) {
+            values.insert(name, quote(shell, &value_of(&name[1..name.len() - 1])?));
+        }
+        line.push_str(&values[name]);
+        last = found.end();
+    }
+    line.push_str(&command[last..]);
+    Ok(line)
+}
+
+static BASH_WIDGET: &str = r#"__dumpb_widget() {
+    local selected
+    selected="$(dumpb pick --line bash)" || return
+    READLINE_LINE="${READLINE_LINE:0:$READLINE_POINT}${selected}${READLINE_LINE:$READLINE_POINT}"
+    READLINE_POINT=$(( READLINE_POINT + ${#selected} ))
+}
+bind -m emacs-standard -x '"\C-g": __dumpb_widget'
+bind -m vi-insert -x '"\C-g": __dumpb_widget'
+"#;
+
+static ZSH_WIDGET: &str = r#"__dumpb_widget() {
+    local selected
+    selected="$(dumpb pick --line zsh)"
+    if [[ $? -eq 0 && -n $selected ]]; then
+        LBUFFER+="$selected"
+    fi
+    zle reset-prompt
+}
+zle -N __dumpb_widget
+bindkey -M emacs '^G' __dumpb_widget
+bindkey -M viins '^G' __dumpb_widget
+"#;
+
+static FISH_WIDGET: &str = r#"function __dumpb_widget
+    set -l selected (dumpb pick --line fish)
+    and commandline --insert -- "$selected"
+    commandline --function repaint
+end
+bind \cg __dumpb_widget
+if bind -M insert >/dev/null 2>&1
+    bind -M insert \cg __dumpb_widget
+end
+"#;
+
+/// Script binding Ctrl-G to the picker, which inserts the chosen record at the cursor instead of executing it.
+/// Cancelled picker exits with code 130 and leaves the command line untouched
+pub fn init_script(shell: Shell) -> String {
+    let shell_name = format!("{:?}", shell).to_lowercase();
+    let widget = match shell {
+        Shell::Bash => BASH_WIDGET,
+        Shell::Zsh => ZSH_WIDGET,
+        Shell::Fish => FISH_WIDGET,
+    };
+    format!("# Key bindings of dumpb, generated by `dumpb shell-init {}`\n{}", shell_name, widget)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::record::domain::entities::RecordMetadata;
+
+    use super::*;
+
+    #[test]
+    fn words_are_quoted_for_every_shell() {
+        assert_eq!(quote(Shell::Bash, "it's"), r"'it'\''s'");
+        assert_eq!(quote(Shell::Fish, r"it's \n"), r"'it\'s \\n'");
+    }
+
+    #[test]
+    fn keys_are_turned_into_unique_function_names() {
+        let taken: HashSet<String> = vec!["db_migrate".to_string()].into_iter().collect();
+        assert_eq!(function_name("db:migrate", &taken), "db_migrate-2");
+        assert_eq!(function_name("2fa", &HashSet::new()), "_2fa");
+    }
+
+    #[test]
+    fn function_forwards_arguments_with_environment_and_directory() {
+        let mut metadata = RecordMetadata { cwd: Some("/srv/app".to_string()), ..RecordMetadata::default() };
+        metadata.env.insert("MODE".to_string(), "dev".to_string());
+        let records = vec![Record::with_metadata("up", "docker compose up 'my app'", metadata)];
+
+        let bash = export_script(&records, Shell::Bash);
+        assert!(bash.contains("up() {\n    ( cd -- '/srv/app' || exit; command env 'MODE=dev' 'docker' 'compose' 'up' 'my app' \"$@\" )\n}"));
+        let fish = export_script(&records, Shell::Fish);
+        assert!(fish.contains("function up\n"));
+        assert!(fish.contains("    command env 'MODE=dev' 'docker' 'compose' 'up' 'my app' $argv\n"));
+    }
+
+    #[test]
+    fn placeholders_become_positional_arguments() {
+        let records = vec![Record::new(&"push".to_string(), &"git push <remote> <branch>:<branch>".to_string())];
+        let bash = export_script(&records, Shell::Bash);
+        assert!(bash.contains("    command 'git' 'push' \"${1}\" \"${2}\"':'\"${2}\" \"${@:3}\"\n"));
+        let fish = export_script(&records, Shell::Fish);
+        assert!(fish.contains("    command 'git' 'push' $argv[1] $argv[2]':'$argv[2] $argv[3..-1]\n"));
+    }
+
+    #[test]
+    fn workflows_call_step_functions_and_broken_records_are_skipped() {
+        let steps = RecordMetadata { steps: vec!["build".to_string(), "db:migrate".to_string()], ..RecordMetadata::default() };
+        let records = vec![
+            Record::new(&"build".to_string(), &"cargo build".to_string()),
+            Record::new(&"db:migrate".to_string(), &"diesel migration run".to_string()),
+            Record::with_metadata("release", "build -> db:migrate", steps),
+            Record::new(&"broken".to_string(), &"echo 'unclosed".to_string()),
+        ];
+        let script = export_script(&records, Shell::Zsh);
+        assert!(script.contains("release() {\n    build && db_migrate\n}"));
+        assert!(script.contains("# Record \"broken\" is skipped: could not split command"));
+    }
+
+    #[test]
+    fn prompt_line_has_directory_and_environment_and_runs_scripts_with_dumpb() {
+        let mut metadata = RecordMetadata { cwd: Some("/srv/app".to_string()), ..RecordMetadata::default() };
+        metadata.env.insert("MODE".to_string(), "it's".to_string());
+        let record = Record::with_metadata("up", "docker compose up", metadata);
+        let mut no_values = |name: &str| -> Result<String, Error> { panic!("{} is asked for", name) };
+        assert_eq!(
+            prompt_line(Shell::Bash, &record, &mut no_values).unwrap(),
+            r"cd -- '/srv/app' && env 'MODE=it'\''s' docker compose up"
+        );
+        assert_eq!(
+            prompt_line(Shell::Fish, &record, &mut no_values).unwrap(),
+            r"cd -- '/srv/app'; and env 'MODE=it\'s' docker compose up"
+        );
+
+        let script = Record::new(&"it's".to_string(), &"#!/bin/sh\necho <name>\n".to_string());
+        assert_eq!(prompt_line(Shell::Zsh, &script, &mut no_values).unwrap(), r"dumpb exec -- 'it'\''s'");
+        assert!(init_script(Shell::Fish).contains("dumpb pick --line fish"));
+    }
+
+    #[test]
+    fn placeholders_are_filled_in_before_insertion() {
+        let record = Record::new(&"cp".to_string(), &"cp <src> <dest>.bak <src>".to_string());
+        let mut asked = Vec::new();
+        let line = prompt_line(Shell::Bash, &record, &mut |name| {
+            asked.push(name.to_string());
+            Ok(format!("{} file's", name))
+        });
+        assert_eq!(line.unwrap(), r"cp 'src file'\''s' 'dest file'\''s'.bak 'src file'\''s'");
+        assert_eq!(asked, vec!["src", "dest"]);
+
+        let cancelled = prompt_line(Shell::Bash, &record, &mut |_| Err(Error::Exec("Cancelled".to_string())));
+        assert!(cancelled.is_err());
+    }
+
+    #[test]
+    fn scripts_are_written_into_temporary_file() {
+        let metadata = RecordMetadata { interpreter: Some("python3".to_string()), ..RecordMetadata::default() };
+        let records = vec![Record::with_metadata("hello", "print('hi')\n", metadata)];
+        let script = export_script(&records, Shell::Bash);
+        assert!(script.contains("    printf '%s' 'print('\\''hi'\\'')\n' > \"$script\"\n"));
+        assert!(script.contains("    command 'python3' \"$script\" \"$@\"\n"));
+    }
+}
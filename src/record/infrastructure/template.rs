@@ -0,0 +1,203 @@
+use crate::record::domain::entities::Record;
+use crate::shared::domain::error::Error;
+
+/// Fields of record which can be used in templates
+const FIELDS: &[&str] = &[
+    "key",
+    "value",
+    "description",
+    "tags",
+    "cwd",
+    "env",
+    "interpreter",
+    "steps",
+    "timeout_ms",
+    "retries",
+    "retry_delay_ms",
+    "last_output",
+];
+
+#[derive(Debug, PartialEq)]
+enum Filter {
+    /// Keep at most given number of characters, cut text ends with "…"
+    Truncate(usize),
+    /// Join lines with spaces
+    OneLine,
+    /// Quote for POSIX shells, so that text is passed as a single word
+    ShellQuote,
+}
+
+impl Filter {
+    fn parse(text: &str) -> Result<Filter, Error> {
+        let (name, argument) = match text.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim())),
+            None => (text.trim(), None),
+        };
+        match (name, argument) {
+            ("truncate", Some(length)) => length
+                .parse()
+                .map(Filter::Truncate)
+                .map_err(|_| Error::Parse(format!("Length of truncate should be a number, not \"{}\"", length))),
+            ("truncate", None) => Err(Error::Parse("Filter truncate needs length, e.g. \"truncate:20\"".to_string())),
+            ("oneline", None) => Ok(Filter::OneLine),
+            ("shell-quote", None) => Ok(Filter::ShellQuote),
+            ("oneline", Some(_)) | ("shell-quote", Some(_)) => Err(Error::Parse(format!("Filter {} takes no argument", name))),
+            _ => Err(Error::Parse(format!("Unknown filter \"{}\", use truncate, oneline or shell-quote", name))),
+        }
+    }
+
+    fn apply(&self, text: String) -> String {
+        match self {
+            Filter::Truncate(length) if text.chars().count() > *length => {
+                let kept: String = text.chars().take(length.saturating_sub(1)).collect();
+                format!("{}…", kept)
+            }
+            Filter::Truncate(_) => text,
+            Filter::OneLine => text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" "),
+            Filter::ShellQuote => shell_quote(&text),
+        }
+    }
+}
+
+fn shell_quote(text: &str) -> String {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c);
+    if !text.is_empty() && text.chars().all(is_safe) {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Part {
+    Text(String),
+    Field { name: String, filters: Vec<Filter> },
+}
+
+/// Text with `{{field|filter}}` placeholders rendered for every record, e.g. `{{key}}\t{{value|oneline}}`.
+/// Escapes `\t`, `\n` and `\\` are replaced, so that tabs and newlines can be typed in the shell
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// Replace escapes of literal text
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('\\') => unescaped.push('\\'),
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, Error> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Text(unescape(&rest[..start])));
+            }
+            let end = rest[start..].find("}}").ok_or_else(|| Error::Parse(format!("Template has unclosed \"{{{{\": {}", text)))?;
+            let mut expression = rest[start + 2..start + end].split('|');
+            let name = expression.next().unwrap_or_default().trim().to_string();
+            if !FIELDS.contains(&name.as_str()) {
+                return Err(Error::Parse(format!("Unknown field \"{}\" in template, use one of: {}", name, FIELDS.join(", "))));
+            }
+            let filters = expression.map(Filter::parse).collect::<Result<_, _>>()?;
+            parts.push(Part::Field { name, filters });
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(unescape(rest)));
+        }
+        Ok(Template { parts })
+    }
+
+    fn field(record: &Record, name: &str) -> String {
+        let metadata = &record.metadata;
+        let number = |number: Option<u64>| number.map(|number| number.to_string()).unwrap_or_default();
+        match name {
+            "key" => record.key.to_string(),
+            "value" => record.value.to_string(),
+            "description" => metadata.description.clone().unwrap_or_default(),
+            "tags" => metadata.tags.join(","),
+            "cwd" => metadata.cwd.clone().unwrap_or_default(),
+            "env" => metadata.env.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(" "),
+            "interpreter" => metadata.interpreter.clone().unwrap_or_default(),
+            "steps" => metadata.steps.join(","),
+            "timeout_ms" => number(metadata.timeout_ms),
+            "retries" => number(metadata.retries.map(u64::from)),
+            "retry_delay_ms" => number(metadata.retry_delay_ms),
+            "last_output" => metadata.last_output.clone().unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    pub fn render(&self, record: &Record) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_string(),
+                Part::Field { name, filters } => {
+                    filters.iter().fold(Template::field(record, name), |text, filter| filter.apply(text))
+                }
+            })
+            .collect()
+    }
+
+    /// Every record rendered on its own line
+    pub fn render_all(&self, records: &[Record]) -> String {
+        records.iter().map(|record| self.render(record)).collect::<Vec<_>>().join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::record::domain::entities::RecordMetadata;
+
+    use super::*;
+
+    fn record() -> Record {
+        let metadata = RecordMetadata { tags: vec!["dev".to_string(), "docker".to_string()], ..RecordMetadata::default() };
+        Record::with_metadata("up", "docker compose up\n  --build\n", metadata)
+    }
+
+    #[test]
+    fn fields_and_escapes_are_rendered() {
+        let template = Template::parse(r"{{key}}\t{{ tags }}\t[{{description}}]\\n").unwrap();
+        assert_eq!(template.render(&record()), "up\tdev,docker\t[]\\n");
+    }
+
+    #[test]
+    fn filters_are_applied_in_order() {
+        assert_eq!(Template::parse("{{value|oneline}}").unwrap().render(&record()), "docker compose up --build");
+        assert_eq!(Template::parse("{{value | oneline | truncate:10}}").unwrap().render(&record()), "docker co…");
+        assert_eq!(Template::parse("{{key|shell-quote}}").unwrap().render(&record()), "up");
+        let quoted = Template::parse("{{value|oneline|shell-quote}}").unwrap();
+        assert_eq!(quoted.render(&Record::new(&"k".to_string(), &"echo 'hi'".to_string())), r"'echo '\''hi'\'''");
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(Template::parse("{{name}}").unwrap_err().message().starts_with("Unknown field \"name\""));
+        assert!(Template::parse("{{key|upper}}").unwrap_err().message().starts_with("Unknown filter \"upper\""));
+        assert!(Template::parse("{{key|truncate:x}}").is_err());
+        assert!(Template::parse("{{key|truncate}}").is_err());
+        assert!(Template::parse("{{key").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt::{self, Display};
+
+/// Error of any command, its kind decides the exit code of the application
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// Arguments make no sense together or for the record, e.g. empty value
+    Invalid(String),
+    /// Record, history entry, job or file does not exist
+    NotFound(String),
+    /// Record with the same key exists already
+    AlreadyExists(String),
+    /// Text could not be understood, e.g. imported file, duration or template
+    Parse(String),
+    /// Storage could not be read or written
+    Storage(String),
+    /// Command, editor or terminal could not be started or failed
+    Exec(String),
+}
+
+impl Error {
+    /// Exit code of the application failing with this error. Executed commands exit with their own codes
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Invalid(_) => 2,
+            Error::NotFound(_) => 3,
+            Error::AlreadyExists(_) => 4,
+            Error::Parse(_) => 5,
+            Error::Storage(_) => 6,
+            Error::Exec(_) => 7,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Invalid(message)
+            | Error::NotFound(message)
+            | Error::AlreadyExists(message)
+            | Error::Parse(message)
+            | Error::Storage(message)
+            | Error::Exec(message) => message,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn every_kind_has_own_exit_code() {
+        let errors = [
+            Error::Invalid(String::new()),
+            Error::NotFound(String::new()),
+            Error::AlreadyExists(String::new()),
+            Error::Parse(String::new()),
+            Error::Storage(String::new()),
+            Error::Exec(String::new()),
+        ];
+        let mut codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+        codes.dedup();
+        assert_eq!(codes, vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(Error::NotFound("Key \"a\" does not exist".to_string()).to_string(), "Key \"a\" does not exist");
+    }
+}
@@ -1,28 +1,249 @@
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use structopt::StructOpt;
 
+use crate::record::application::queries::ConflictStrategy;
+use crate::record::infrastructure::cheat_sheet::Grouping;
+use crate::record::infrastructure::codecs::Format;
+use crate::record::infrastructure::importers::Source;
+use crate::record::infrastructure::output::OutputFormat;
+use crate::shared::infrastructure::shell_history::Shell;
+
 #[derive(Debug, StructOpt, Clone, PartialEq)]
 pub enum DumpBufferCLI {
     #[structopt(setting = structopt::clap::AppSettings::AllowLeadingHyphen)]
     #[structopt(setting = structopt::clap::AppSettings::TrailingVarArg)]
     /// Add new record to the storage
     Add {
+        #[structopt(long)]
+        /// Human readable explanation of what record does
+        description: Option<String>,
+        #[structopt(long)]
+        /// Directory to execute record in
+        cwd: Option<String>,
+        #[structopt(long, number_of_values = 1, parse(try_from_str = parse_env_var))]
+        /// Environment variable in form NAME=VALUE to set for execution, can be repeated
+        env: Vec<(String, String)>,
+        #[structopt(long = "tag", number_of_values = 1)]
+        /// Tag to group records with, can be repeated
+        tags: Vec<String>,
+        #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+        /// Default execution timeout, e.g. "30s" or "2m"
+        timeout: Option<Duration>,
+        #[structopt(long)]
+        /// Default number of retries after failed execution
+        retries: Option<u32>,
+        #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+        /// Default pause between retries, e.g. "2s"
+        retry_delay: Option<Duration>,
+        #[structopt(long)]
+        /// Program executing the value as a script, e.g. "bash" or "python3"
+        interpreter: Option<String>,
+        #[structopt(long, alias = "file", parse(from_os_str))]
+        /// Read value from a file as is, guessing interpreter of scripts from extension
+        from_file: Option<PathBuf>,
+        #[structopt(long, conflicts_with = "from-file")]
+        /// Use the previous command from shell history as value
+        last: bool,
         key: String,
-        #[structopt(required = true, parse(from_os_str))]
+        #[structopt(
+            required_unless_one = &["from-file", "last"],
+            conflicts_with_all = &["from-file", "last"],
+            parse(from_os_str)
+        )]
+        /// Value to store, "-" reads it from stdin
         value: Vec<OsString>,
     },
+    /// Save the previous command from shell history as a record
+    SaveLast {
+        key: String,
+    },
+    /// Turn the most repeated commands from shell history into records
+    ImportHistory {
+        #[structopt(long)]
+        /// Import given number of the most repeated commands with generated keys, without asking
+        top: Option<usize>,
+        #[structopt(long, default_value = "20")]
+        /// Shorter commands are not offered for import
+        min_length: usize,
+        #[structopt(long, possible_values = &["bash", "zsh", "fish"])]
+        /// Shell whose history is read, the current one by default
+        shell: Option<Shell>,
+        #[structopt(long, parse(from_os_str))]
+        /// Read history from a specific file
+        history_file: Option<PathBuf>,
+    },
     /// Get record with specific key
     Get {
         key: String,
+        #[structopt(long)]
+        /// Accept unambiguous prefix or fuzzy match of the key
+        fuzzy: bool,
+        #[structopt(long, possible_values = &["json", "yaml", "table", "plain", "tsv"])]
+        /// Output format, only the value by default
+        format: Option<OutputFormat>,
+        #[structopt(long, conflicts_with = "format")]
+        /// Render the record with a template, e.g. '{{key}}\t{{value|oneline}}'
+        template: Option<String>,
+    },
+    /// Show record with specific key together with its metadata
+    Show {
+        key: String,
+        #[structopt(long)]
+        /// Accept unambiguous prefix or fuzzy match of the key
+        fuzzy: bool,
+        #[structopt(long, possible_values = &["json", "yaml", "table", "plain", "tsv"])]
+        /// Output format, a field per line by default
+        format: Option<OutputFormat>,
+    },
+    /// Change value of record with specific key in the configured editor, $VISUAL or $EDITOR
+    Edit {
+        key: String,
+        #[structopt(long)]
+        /// Accept unambiguous prefix or fuzzy match of the key
+        fuzzy: bool,
     },
     /// Execute record with specific key
     Exec {
-        key: String,
+        #[structopt(required_unless = "tag")]
+        /// Key of record to execute, several keys can be given together with --parallel
+        keys: Vec<String>,
+        #[structopt(long)]
+        /// Accept unambiguous prefixes or fuzzy matches of the keys
+        fuzzy: bool,
+        #[structopt(long)]
+        /// Save stdout of the command into record with specific key,
+        /// or into last-run metadata of executed record if key is omitted
+        save_output: Option<Option<String>>,
+        #[structopt(long, requires = "save-output")]
+        /// Print saved output to the terminal as well
+        tee: bool,
+        #[structopt(long, requires = "save-output")]
+        /// Save stderr together with stdout
+        with_stderr: bool,
+        #[structopt(long, default_value = "65536")]
+        /// Maximum size of saved output in bytes, the rest is truncated
+        output_limit: usize,
+        #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+        /// Kill command if it runs longer, e.g. "30s" or "2m"
+        timeout: Option<Duration>,
+        #[structopt(long)]
+        /// Number of retries after failed execution
+        retries: Option<u32>,
+        #[structopt(long, parse(try_from_str = humantime::parse_duration))]
+        /// Pause between retries, e.g. "2s"
+        retry_delay: Option<Duration>,
+        #[structopt(long)]
+        /// Run remaining workflow steps even after one of them failed
+        continue_on_error: bool,
+        #[structopt(long)]
+        /// Execute several records concurrently, prefixing their output with record key
+        parallel: bool,
+        #[structopt(long, requires = "parallel")]
+        /// Execute all records with this tag
+        tag: Option<String>,
+        #[structopt(long, requires = "parallel")]
+        /// Maximum number of records executed at once
+        jobs: Option<usize>,
+        #[structopt(
+            long,
+            conflicts_with_all = &["parallel", "save-output", "continue-on-error", "timeout", "retries", "retry-delay"]
+        )]
+        /// Start command in background, writing its output into a log file. Timeouts and retries are not applied to it
+        detach: bool,
+    },
+    /// Manage workflows composed of other records
+    Flow {
+        #[structopt(subcommand)]
+        command: FlowCommand,
     },
     /// List all available records
     List {
         #[structopt(long)]
+        /// Show only keys of records
         keys_only: bool,
+        #[structopt(long, possible_values = &["json", "yaml", "table", "plain", "tsv"])]
+        /// Output format, JSON by default
+        format: Option<OutputFormat>,
+        #[structopt(long, conflicts_with_all = &["format", "keys-only"])]
+        /// Render every record with a template, e.g. '{{key}}\t{{value|oneline}}'
+        template: Option<String>,
+    },
+    /// Choose a record in an interactive fuzzy finder and print its value
+    Pick {
+        #[structopt(short, long, default_value = "")]
+        /// Initial query
+        query: String,
+        #[structopt(long)]
+        /// Copy value into clipboard instead of printing it
+        copy: bool,
+        #[structopt(long, conflicts_with = "copy")]
+        /// Execute chosen record
+        exec: bool,
+        #[structopt(long, possible_values = &["bash", "zsh", "fish"], conflicts_with_all = &["copy", "exec"])]
+        /// Print chosen record as a command line for the shell prompt, used by shell-init key bindings
+        line: Option<Shell>,
+    },
+    /// Search records by key, value or description
+    Search {
+        /// Text to look for, matched as a substring by default
+        pattern: String,
+        #[structopt(short, long)]
+        /// Treat pattern as a regular expression
+        regex: bool,
+        #[structopt(short, long)]
+        /// Ignore case of letters
+        ignore_case: bool,
+        #[structopt(long)]
+        /// Render every record with a template, e.g. '{{key}}\t{{value|oneline}}'
+        template: Option<String>,
+    },
+    /// Print completion script for the shell, e.g. `dumpb completions bash > /etc/bash_completion.d/dumpb`
+    Completions {
+        #[structopt(possible_values = &["bash", "zsh", "fish"])]
+        shell: Shell,
+    },
+    /// Browse, filter, edit, delete and execute records in a full-screen terminal UI
+    Tui,
+    /// Print key bindings inserting a picked record into the command line, e.g. `eval "$(dumpb shell-init bash)"`
+    ShellInit {
+        #[structopt(possible_values = &["bash", "zsh", "fish"])]
+        shell: Shell,
+    },
+    /// Export all records, e.g. to move them to another machine
+    Export {
+        #[structopt(long, possible_values = &["json", "yaml", "csv", "markdown"])]
+        /// Format of exported records, guessed from output file extension and JSON by default
+        format: Option<Format>,
+        #[structopt(long, default_value = "tag", possible_values = &["tag", "namespace"])]
+        /// Split markdown cheat sheet into sections by tag or by key prefix before ":", "/" or "."
+        group_by: Grouping,
+        #[structopt(long = "as", possible_values = &["bash", "zsh", "fish"], conflicts_with = "format")]
+        /// Generate a script for the shell, which defines a function per record
+        shell: Option<Shell>,
+        #[structopt(short, long, parse(from_os_str))]
+        /// Write records into file instead of stdout
+        output: Option<PathBuf>,
+    },
+    /// Import records from a file created by export
+    Import {
+        #[structopt(parse(from_os_str))]
+        /// File to import, "-" reads stdin
+        file: PathBuf,
+        #[structopt(long, possible_values = &["json", "yaml", "csv"])]
+        /// Format of the file, detected automatically by default
+        format: Option<Format>,
+        #[structopt(long, conflicts_with = "format", possible_values = &["navi", "pet", "aliases"])]
+        /// Convert snippets of other manager: navi cheatsheet, pet snippets or shell aliases
+        from: Option<Source>,
+        #[structopt(long, default_value = "fail", possible_values = &["skip", "overwrite", "rename", "fail"])]
+        /// What to do with records whose keys are already used
+        on_conflict: ConflictStrategy,
+        #[structopt(long)]
+        /// Only show what would be imported
+        dry_run: bool,
     },
     /// Delete record from storage by key or all records at once
     #[structopt(name = "rm")]
@@ -33,6 +254,92 @@ pub enum DumpBufferCLI {
         #[structopt(long, conflicts_with = "key")]
         /// Delete all records
         all: bool,
+    },
+    /// Show history of executed records
+    History {
+        #[structopt(long)]
+        /// Show only executions of record with specific key
+        key: Option<String>,
+        #[structopt(long)]
+        /// Show only executions which did not succeed
+        failed: bool,
+        #[structopt(subcommand)]
+        command: Option<HistoryCommand>,
+    },
+    /// List records started in background
+    Jobs {
+        #[structopt(long)]
+        /// Show only jobs which are still running
+        running: bool,
+    },
+    /// Show output of a background job
+    Logs {
+        job: u32,
+    },
+    /// Stop a running background job
+    Kill {
+        job: u32,
+    },
+    /// Manage settings of ~/.config/dumpb/config.toml
+    Config {
+        #[structopt(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, StructOpt, Clone, PartialEq)]
+pub enum FlowCommand {
+    /// Add workflow executing records with given keys one by one
+    Add {
+        key: String,
+        #[structopt(required = true)]
+        steps: Vec<String>,
+    },
+}
+
+#[derive(Debug, StructOpt, Clone, PartialEq)]
+pub enum HistoryCommand {
+    /// Run command from history entry with specific number again
+    Rerun {
+        number: usize,
+    },
+}
+
+#[derive(Debug, StructOpt, Clone, PartialEq)]
+pub enum ConfigCommand {
+    /// Print current value of a setting, e.g. `output.format`
+    Get {
+        name: String,
+    },
+    /// Change a setting or add an alias, e.g. `aliases.ls "list --format table"`
+    Set {
+        name: String,
+        value: String,
+    },
+    /// Remove a setting or an alias from the file
+    Unset {
+        name: String,
+    },
+    /// List all settings with their current values, and aliases
+    List,
+}
+
+/// Whether `name` is a subcommand, aliases can not replace subcommands
+pub fn is_command(name: &str) -> bool {
+    use structopt::clap::ErrorKind;
+    match DumpBufferCLI::clap().get_matches_from_safe(["dumpb", name]) {
+        Err(e) => !matches!(
+            e.kind,
+            ErrorKind::UnrecognizedSubcommand | ErrorKind::InvalidSubcommand | ErrorKind::UnknownArgument
+        ),
+        Ok(_) => true,
+    }
+}
+
+fn parse_env_var(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("Environment variable \"{}\" should be in form NAME=VALUE", raw)),
     }
 }
 
@@ -40,7 +347,7 @@ impl DumpBufferCLI {
     #[allow(dead_code)]
     pub fn joined_value(&self, separator: &str) -> Option<String> {
         match self {
-            DumpBufferCLI::Add { key: _, value } => {
+            DumpBufferCLI::Add { value, .. } => {
                 let vals: Vec<String> = value
                     .iter()
                     .map(|v| {
@@ -64,6 +371,16 @@ mod test {
         assert_eq!(
             DumpBufferCLI::from_iter(&["test", "add", "some-key", "some-value"]),
             DumpBufferCLI::Add {
+                description: None,
+                cwd: None,
+                env: vec![],
+                tags: vec![],
+                timeout: None,
+                retries: None,
+                retry_delay: None,
+                interpreter: None,
+                from_file: None,
+                last: false,
                 key: "some-key".to_string(),
                 value: [OsString::from("some-value")].to_vec()
             }
@@ -75,6 +392,16 @@ mod test {
         assert_eq!(
             DumpBufferCLI::from_iter(&["test", "add", "key", "value", "-c", "hey"]),
             DumpBufferCLI::Add {
+                description: None,
+                cwd: None,
+                env: vec![],
+                tags: vec![],
+                timeout: None,
+                retries: None,
+                retry_delay: None,
+                interpreter: None,
+                from_file: None,
+                last: false,
                 key: "key".to_string(),
                 value: [
                     OsString::from("value"),
@@ -86,21 +413,112 @@ mod test {
         );
     }
 
+    #[test]
+    fn add_works_with_cwd_and_env() {
+        let args = [
+            "test", "add", "--cwd", "/srv", "--env", "A=1", "--env", "B=x=y", "key", "ls", "--env", "C=2",
+        ];
+        assert_eq!(
+            DumpBufferCLI::from_iter(&args),
+            DumpBufferCLI::Add {
+                description: None,
+                cwd: Some("/srv".to_string()),
+                env: vec![("A".to_string(), "1".to_string()), ("B".to_string(), "x=y".to_string())],
+                tags: vec![],
+                timeout: None,
+                retries: None,
+                retry_delay: None,
+                interpreter: None,
+                from_file: None,
+                last: false,
+                key: "key".to_string(),
+                value: ["ls", "--env", "C=2"].iter().map(OsString::from).collect()
+            }
+        );
+    }
+
+    #[test]
+    fn add_accepts_script_file_instead_of_value() {
+        match DumpBufferCLI::from_iter(&["test", "add", "key", "--from-file", "build.py"]) {
+            DumpBufferCLI::Add { key, from_file, value, .. } => {
+                assert_eq!(key, "key");
+                assert_eq!(from_file, Some(PathBuf::from("build.py")));
+                assert!(value.is_empty());
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        match DumpBufferCLI::from_iter(&["test", "add", "--interpreter", "python3", "key", "print(1)"]) {
+            DumpBufferCLI::Add { interpreter, .. } => assert_eq!(interpreter, Some("python3".to_string())),
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "add", "--from-file", "a.sh", "key", "value"]).is_err());
+    }
+
+    #[test]
+    fn add_accepts_file_alias_and_stdin_marker() {
+        match DumpBufferCLI::from_iter(&["test", "add", "key", "--file", "notes.txt"]) {
+            DumpBufferCLI::Add { from_file, .. } => assert_eq!(from_file, Some(PathBuf::from("notes.txt"))),
+            other => panic!("Unexpected command {:?}", other),
+        }
+        match DumpBufferCLI::from_iter(&["test", "add", "key", "-"]) {
+            DumpBufferCLI::Add { value, .. } => assert_eq!(value, vec![OsString::from("-")]),
+            other => panic!("Unexpected command {:?}", other),
+        }
+    }
+
+    #[test]
+    fn add_last_replaces_value() {
+        match DumpBufferCLI::from_iter(&["test", "add", "key", "--last"]) {
+            DumpBufferCLI::Add { key, last, value, .. } => {
+                assert_eq!(key, "key");
+                assert!(last);
+                assert!(value.is_empty());
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "add", "--last", "key", "value"]).is_err());
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "save-last", "key"]),
+            DumpBufferCLI::SaveLast { key: "key".to_string() }
+        );
+    }
+
+    #[test]
+    fn import_history_is_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "import-history", "--top", "5", "--shell", "zsh"]),
+            DumpBufferCLI::ImportHistory { top: Some(5), min_length: 20, shell: Some(Shell::Zsh), history_file: None }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "import-history", "--shell", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn add_fails_for_malformed_env() {
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "add", "--env", "NOVALUE", "key", "ls"]).is_err());
+    }
+
     #[test]
     fn get_works_with_key() {
         assert_eq!(
             DumpBufferCLI::from_iter(&["test", "get", "key-name"]),
             DumpBufferCLI::Get {
-                key: "key-name".to_string()
+                key: "key-name".to_string(),
+                fuzzy: false,
+                format: None,
+                template: None
             }
         );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "get", "--fuzzy", "kn"]),
+            DumpBufferCLI::Get { key: "kn".to_string(), fuzzy: true, format: None, template: None }
+        );
     }
 
     #[test]
     fn list_is_parsed_correctly_without_flag() {
         assert_eq!(
             DumpBufferCLI::from_iter(&["test", "list"]),
-            DumpBufferCLI::List { keys_only: false }
+            DumpBufferCLI::List { keys_only: false, format: None, template: None }
         );
     }
 
@@ -108,8 +526,262 @@ mod test {
     fn list_is_parsed_correctly_with_flag() {
         assert_eq!(
             DumpBufferCLI::from_iter(&["test", "list", "--keys-only"]),
-            DumpBufferCLI::List { keys_only: true }
+            DumpBufferCLI::List { keys_only: true, format: None, template: None }
+        );
+    }
+
+    #[test]
+    fn output_format_is_parsed_for_read_commands() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "list", "--format", "tsv"]),
+            DumpBufferCLI::List { keys_only: false, format: Some(OutputFormat::Tsv), template: None }
+        );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "show", "--format", "yaml", "up"]),
+            DumpBufferCLI::Show { key: "up".to_string(), fuzzy: false, format: Some(OutputFormat::Yaml) }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "get", "--format", "xml", "up"]).is_err());
+    }
+
+    #[test]
+    fn template_conflicts_with_format() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "list", "--template", "{{key}}"]),
+            DumpBufferCLI::List { keys_only: false, format: None, template: Some("{{key}}".to_string()) }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "list", "--template", "{{key}}", "--format", "json"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "list", "--template", "{{key}}", "--keys-only"]).is_err());
+    }
+
+    #[test]
+    fn pick_is_parsed_correctly_with_flags() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "pick", "-q", "dock", "--exec"]),
+            DumpBufferCLI::Pick { query: "dock".to_string(), copy: false, exec: true, line: None }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "pick", "--copy", "--exec"]).is_err());
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "pick", "--line", "zsh"]),
+            DumpBufferCLI::Pick { query: String::new(), copy: false, exec: false, line: Some(Shell::Zsh) }
+        );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "shell-init", "fish"]),
+            DumpBufferCLI::ShellInit { shell: Shell::Fish }
+        );
+    }
+
+    #[test]
+    fn edit_and_tui_are_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "edit", "--fuzzy", "dep"]),
+            DumpBufferCLI::Edit { key: "dep".to_string(), fuzzy: true }
+        );
+        assert_eq!(DumpBufferCLI::from_iter(&["test", "tui"]), DumpBufferCLI::Tui);
+    }
+
+    #[test]
+    fn search_is_parsed_correctly_with_flags() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "search", "-ri", "^docker"]),
+            DumpBufferCLI::Search { pattern: "^docker".to_string(), regex: true, ignore_case: true, template: None }
+        );
+    }
+
+    #[test]
+    fn exec_is_parsed_correctly_without_flags() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "exec", "key"]),
+            DumpBufferCLI::Exec {
+                keys: vec!["key".to_string()],
+                fuzzy: false,
+                save_output: None,
+                tee: false,
+                with_stderr: false,
+                output_limit: 65536,
+                timeout: None,
+                retries: None,
+                retry_delay: None,
+                continue_on_error: false,
+                parallel: false,
+                tag: None,
+                jobs: None,
+                detach: false
+            }
+        );
+    }
+
+    #[test]
+    fn exec_parallel_accepts_several_keys_and_tag() {
+        let args = ["test", "exec", "--parallel", "--jobs", "2", "--tag", "dev", "web", "api"];
+        match DumpBufferCLI::from_iter(&args) {
+            DumpBufferCLI::Exec { keys, parallel, tag, jobs, .. } => {
+                assert_eq!(keys, vec!["web".to_string(), "api".to_string()]);
+                assert!(parallel);
+                assert_eq!(tag, Some("dev".to_string()));
+                assert_eq!(jobs, Some(2));
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--parallel", "--tag", "dev"]).is_ok());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--tag", "dev"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec"]).is_err());
+    }
+
+    #[test]
+    fn flow_add_is_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "flow", "add", "release", "build", "test"]),
+            DumpBufferCLI::Flow {
+                command: FlowCommand::Add {
+                    key: "release".to_string(),
+                    steps: vec!["build".to_string(), "test".to_string()]
+                }
+            }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "flow", "add", "release"]).is_err());
+    }
+
+    #[test]
+    fn exec_parses_timeout_and_retries() {
+        let args = ["test", "exec", "key", "--timeout", "1m 30s", "--retries", "3", "--retry-delay", "500ms"];
+        match DumpBufferCLI::from_iter(&args) {
+            DumpBufferCLI::Exec { timeout, retries, retry_delay, .. } => {
+                assert_eq!(timeout, Some(Duration::from_secs(90)));
+                assert_eq!(retries, Some(3));
+                assert_eq!(retry_delay, Some(Duration::from_millis(500)));
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "key", "--timeout", "soon"]).is_err());
+    }
+
+    #[test]
+    fn exec_save_output_key_is_optional() {
+        match DumpBufferCLI::from_iter(&["test", "exec", "key", "--save-output", "--tee"]) {
+            DumpBufferCLI::Exec { save_output, tee, .. } => {
+                assert_eq!(save_output, Some(None));
+                assert!(tee);
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        match DumpBufferCLI::from_iter(&["test", "exec", "key", "--save-output", "other"]) {
+            DumpBufferCLI::Exec { save_output, .. } => {
+                assert_eq!(save_output, Some(Some("other".to_string())))
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exec_tee_requires_save_output() {
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "key", "--tee"]).is_err());
+    }
+
+    #[test]
+    fn exec_detach_conflicts_with_parallel_and_saved_output() {
+        match DumpBufferCLI::from_iter(&["test", "exec", "--detach", "tunnel"]) {
+            DumpBufferCLI::Exec { keys, detach, .. } => {
+                assert_eq!(keys, vec!["tunnel".to_string()]);
+                assert!(detach);
+            }
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--detach", "--parallel", "a", "b"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--detach", "--save-output", "key"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--detach", "--timeout", "5s", "key"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--detach", "--retries", "2", "key"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "exec", "--detach", "--retry-delay", "1s", "key"]).is_err());
+    }
+
+    #[test]
+    fn job_commands_are_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "jobs", "--running"]),
+            DumpBufferCLI::Jobs { running: true }
+        );
+        assert_eq!(DumpBufferCLI::from_iter(&["test", "logs", "3"]), DumpBufferCLI::Logs { job: 3 });
+        assert_eq!(DumpBufferCLI::from_iter(&["test", "kill", "3"]), DumpBufferCLI::Kill { job: 3 });
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "kill", "first"]).is_err());
+    }
+
+    #[test]
+    fn export_and_import_are_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "export", "--format", "yaml", "-o", "backup.yaml"]),
+            DumpBufferCLI::Export {
+                format: Some(Format::Yaml),
+                group_by: Grouping::Tag,
+                shell: None,
+                output: Some(PathBuf::from("backup.yaml"))
+            }
+        );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "export", "--as", "fish"]),
+            DumpBufferCLI::Export { format: None, group_by: Grouping::Tag, shell: Some(Shell::Fish), output: None }
+        );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "export", "--format", "markdown", "--group-by", "namespace"]),
+            DumpBufferCLI::Export { format: Some(Format::Markdown), group_by: Grouping::Namespace, shell: None, output: None }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "export", "--as", "zsh", "--format", "json"]).is_err());
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "import", "backup.csv", "--on-conflict", "rename", "--dry-run"]),
+            DumpBufferCLI::Import {
+                file: PathBuf::from("backup.csv"),
+                format: None,
+                from: None,
+                on_conflict: ConflictStrategy::Rename,
+                dry_run: true
+            }
+        );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "export", "--format", "xml"]).is_err());
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "import", "f", "--on-conflict", "merge"]).is_err());
+        match DumpBufferCLI::from_iter(&["test", "import", "--from", "navi", "git.cheat"]) {
+            DumpBufferCLI::Import { from, .. } => assert_eq!(from, Some(Source::Navi)),
+            other => panic!("Unexpected command {:?}", other),
+        }
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "import", "--from", "pet", "--format", "json", "f"]).is_err());
+    }
+
+    #[test]
+    fn history_is_parsed_correctly_with_filters() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "history", "--key", "k", "--failed"]),
+            DumpBufferCLI::History {
+                key: Some("k".to_string()),
+                failed: true,
+                command: None
+            }
+        );
+    }
+
+    #[test]
+    fn history_rerun_is_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "history", "rerun", "3"]),
+            DumpBufferCLI::History {
+                key: None,
+                failed: false,
+                command: Some(HistoryCommand::Rerun { number: 3 })
+            }
+        );
+    }
+
+    #[test]
+    fn config_commands_are_parsed_correctly() {
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "config", "set", "aliases.ls", "list --format table"]),
+            DumpBufferCLI::Config {
+                command: ConfigCommand::Set { name: "aliases.ls".to_string(), value: "list --format table".to_string() }
+            }
+        );
+        assert_eq!(
+            DumpBufferCLI::from_iter(&["test", "config", "list"]),
+            DumpBufferCLI::Config { command: ConfigCommand::List }
         );
+        assert!(DumpBufferCLI::from_iter_safe(&["test", "config", "get"]).is_err());
+        assert!(is_command("rm") && is_command("get") && is_command("config"));
+        assert!(!is_command("ls") && !is_command("gte"));
     }
 
     #[test]
@@ -130,8 +802,11 @@ mod test {
     fn joined_value_is_none_for_other_commands() {
         let get_cmd = DumpBufferCLI::Get {
             key: "any".to_string(),
+            fuzzy: false,
+            format: None,
+            template: None,
         };
-        let list_cmd = DumpBufferCLI::List { keys_only: false };
+        let list_cmd = DumpBufferCLI::List { keys_only: false, format: None, template: None };
         assert_eq!(get_cmd.joined_value(" "), None);
         assert_eq!(list_cmd.joined_value(" "), None);
     }
@@ -0,0 +1,113 @@
+use structopt::clap::Shell as ClapShell;
+use structopt::StructOpt;
+
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::cli::DumpBufferCLI;
+use crate::shared::infrastructure::shell_history::Shell;
+
+/// Internal command printing keys of records for completion scripts. It is not a part of `DumpBufferCLI`,
+/// so it is not offered for completion, and clap can not generate bash completion for names with "__"
+pub static COMPLETE_COMMAND: &str = "__complete";
+/// Commands which take key of an existing record as argument
+const KEY_COMMANDS: &[&str] = &["get", "show", "edit", "exec", "rm"];
+
+static BASH_KEYS: &str = r#"
+_dumpb_keys() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    # Keys like "db:migrate" are split by ":" in COMP_WORDS, bash-completion can join them back
+    if declare -F _get_comp_words_by_ref >/dev/null; then
+        _get_comp_words_by_ref -n : cur
+    fi
+    if [[ ${COMP_CWORD} -ge 2 && ${cur} != -* ]]; then
+        case "${COMP_WORDS[1]}" in
+            COMMANDS)
+                COMPREPLY=( $(compgen -W "$(dumpb __complete keys 2>/dev/null)" -- "${cur}") )
+                if declare -F __ltrim_colon_completions >/dev/null; then
+                    __ltrim_colon_completions "${cur}"
+                fi
+                return 0
+                ;;
+        esac
+    fi
+    _dumpb "$@"
+}
+
+complete -F _dumpb_keys -o bashdefault -o default dumpb
+"#;
+
+static ZSH_KEYS: &str = r#"
+_dumpb_keys() {
+    local -a keys
+    keys=( ${(f)"$(dumpb __complete keys 2>/dev/null)"} )
+    compadd -a keys
+}
+
+"#;
+
+static FISH_KEYS: &str = r#"
+complete -c dumpb -n "__fish_seen_subcommand_from COMMANDS" -f -a "(dumpb __complete keys 2>/dev/null)"
+"#;
+
+/// Zsh completes positional arguments with files, arguments of key commands are completed with keys instead
+fn complete_zsh_keys(script: &str) -> String {
+    let mut command: Option<&str> = None;
+    let mut lines = Vec::new();
+    for line in script.lines() {
+        if line.starts_with('(') && line.ends_with(')') {
+            command = Some(line.trim_start_matches('(').trim_end_matches(')'));
+        }
+        let is_key = line.starts_with("':key") && line.ends_with(":_files' \\");
+        if is_key && command.is_some_and(|command| KEY_COMMANDS.contains(&command)) {
+            lines.push(line.replace(":_files' \\", ":_dumpb_keys' \\"));
+        } else {
+            if line == "_dumpb \"$@\"" {
+                lines.push(ZSH_KEYS.trim_start().to_string());
+            }
+            lines.push(line.to_string());
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Completion script generated from the command line definition, where keys of records are completed
+/// with the current keys from the storage
+pub fn completion_script(shell: Shell) -> Result<String, Error> {
+    let clap_shell = match shell {
+        Shell::Bash => ClapShell::Bash,
+        Shell::Zsh => ClapShell::Zsh,
+        Shell::Fish => ClapShell::Fish,
+    };
+    let mut script = Vec::new();
+    DumpBufferCLI::clap().gen_completions_to("dumpb", clap_shell, &mut script);
+    let script = String::from_utf8(script).map_err(|e| Error::Exec(format!("Could not generate completions: {}", e)))?;
+    Ok(match shell {
+        Shell::Bash => {
+            let script = script.replace("complete -F _dumpb -o bashdefault -o default dumpb\n", "");
+            script + &BASH_KEYS.replace("COMMANDS", &KEY_COMMANDS.join("|"))
+        }
+        Shell::Zsh => complete_zsh_keys(&script),
+        Shell::Fish => script + &FISH_KEYS.replace("COMMANDS", &KEY_COMMANDS.join(" ")),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys_are_completed_for_key_commands() {
+        let bash = completion_script(Shell::Bash).unwrap();
+        assert!(bash.contains("get|show|edit|exec|rm)"));
+        assert_eq!(bash.matches("complete -F").count(), 1);
+
+        let zsh = completion_script(Shell::Zsh).unwrap();
+        assert!(zsh.contains("':key:_dumpb_keys' \\"));
+        assert!(zsh.contains("':key -- Delete record by key:_dumpb_keys' \\"));
+        assert!(zsh.contains("':key:_files' \\"), "keys of new workflows are not completed");
+        assert!(zsh.find("_dumpb_keys() {") < zsh.find("\n_dumpb \"$@\""));
+
+        let fish = completion_script(Shell::Fish).unwrap();
+        assert!(fish.contains("__fish_seen_subcommand_from get show edit exec rm\" -f -a \"(dumpb __complete keys"));
+    }
+}
@@ -0,0 +1,325 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::value::{Table, Value};
+
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::cli::is_command;
+use crate::shared::infrastructure::terminal::is_interactive;
+
+/// Variable pointing to another configuration file, e.g. for scripts and tests
+static CONFIG_VARIABLE: &str = "DUMPB_CONFIG";
+/// Section of the file with aliases of subcommands, e.g. `ls = "list --format table"`
+static ALIASES: &str = "aliases";
+
+/// Kind of value a setting takes, values are checked before they are used or saved
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Text,
+    Flag,
+    Choice(&'static [&'static str]),
+}
+
+struct Setting {
+    name: &'static str,
+    kind: Kind,
+    /// Value used when the setting is set neither in environment nor in the file, empty if there is none
+    default: &'static str,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting { name: "store.path", kind: Kind::Text, default: "~" },
+    Setting { name: "store.backend", kind: Kind::Choice(&["file"]), default: "file" },
+    Setting { name: "output.format", kind: Kind::Choice(&["json", "yaml", "table", "plain", "tsv"]), default: "" },
+    Setting { name: "output.color", kind: Kind::Choice(&["auto", "always", "never"]), default: "auto" },
+    Setting { name: "exec.shell", kind: Kind::Text, default: "" },
+    Setting { name: "confirm.remove", kind: Kind::Flag, default: "false" },
+    Setting { name: "confirm.clear", kind: Kind::Flag, default: "false" },
+    Setting { name: "confirm.exec", kind: Kind::Flag, default: "false" },
+    Setting { name: "editor.command", kind: Kind::Text, default: "" },
+];
+
+impl Setting {
+    fn find(name: &str) -> Result<&'static Setting, Error> {
+        SETTINGS.iter().find(|setting| setting.name == name).ok_or_else(|| {
+            let names: Vec<&str> = SETTINGS.iter().map(|setting| setting.name).collect();
+            Error::Invalid(format!("Unknown setting \"{}\", use one of: {}, {}.<name>", name, names.join(", "), ALIASES))
+        })
+    }
+
+    /// Variable overriding the setting, e.g. `DUMPB_OUTPUT_FORMAT` for `output.format`
+    fn variable(&self) -> String {
+        format!("DUMPB_{}", self.name.replace('.', "_").to_uppercase())
+    }
+
+    fn check(&self, value: &str, source: &str) -> Result<(), Error> {
+        let allowed: &[&str] = match self.kind {
+            Kind::Text => return Ok(()),
+            Kind::Flag => &["true", "false"],
+            Kind::Choice(choices) => choices,
+        };
+        if allowed.contains(&value) {
+            Ok(())
+        } else {
+            Err(Error::Invalid(format!(
+                "Setting {} in {} should be one of {}, not \"{}\"",
+                self.name,
+                source,
+                allowed.join(", "),
+                value
+            )))
+        }
+    }
+}
+
+/// Storage of records, chosen with `store.backend`
+#[derive(Debug, PartialEq)]
+pub enum Backend {
+    /// Key-value files in the store directory
+    File,
+}
+
+/// Settings from `~/.config/dumpb/config.toml`, every setting can be overridden by its environment variable
+pub struct Config {
+    path: PathBuf,
+    table: Table,
+    /// Lookup of environment variables, replaced in tests so that they do not depend on the environment
+    variable: fn(&str) -> Option<String>,
+}
+
+impl Config {
+    /// Location of the configuration file, `$DUMPB_CONFIG` takes precedence over `$XDG_CONFIG_HOME` and `~/.config`
+    pub fn location() -> Result<PathBuf, Error> {
+        if let Some(path) = env::var_os(CONFIG_VARIABLE).filter(|path| !path.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+        let config_dir = match env::var_os("XDG_CONFIG_HOME").filter(|path| !path.is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => home_dir()?.join(".config"),
+        };
+        Ok(config_dir.join("dumpb").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config, Error> {
+        Config::read(&Config::location()?, |name| env::var(name).ok())
+    }
+
+    /// Missing file is the same as an empty one, so that everything has its default value
+    fn read(path: &Path, variable: fn(&str) -> Option<String>) -> Result<Config, Error> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(Error::Storage(format!("Could not read {}: {}", path.display(), e))),
+        };
+        let table = toml::from_str(&content)
+            .map_err(|e| Error::Parse(format!("Could not parse {}: {}", path.display(), e)))?;
+        Ok(Config { path: path.to_path_buf(), table, variable })
+    }
+
+    /// Comments and formatting of the file are not kept
+    fn save(&self) -> Result<(), Error> {
+        let content = toml::to_string_pretty(&self.table).map_err(|e| Error::Storage(e.to_string()))?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| Error::Storage(format!("Could not create {}: {}", parent.display(), e)))?;
+        }
+        fs::write(&self.path, content).map_err(|e| Error::Storage(format!("Could not write {}: {}", self.path.display(), e)))
+    }
+
+    /// Value written in the file, numbers and flags are turned into text
+    fn file_value(&self, name: &str) -> Option<String> {
+        let (section, key) = name.split_once('.')?;
+        match self.table.get(section)?.get(key)? {
+            Value::String(value) => Some(value.to_string()),
+            value => Some(value.to_string()),
+        }
+    }
+
+    /// Value of a setting from environment, the file or its default, in this order. Aliases are read only from the file
+    pub fn get(&self, name: &str) -> Result<Option<String>, Error> {
+        if name.starts_with(&format!("{}.", ALIASES)) {
+            return Ok(self.file_value(name));
+        }
+        let setting = Setting::find(name)?;
+        let variable = setting.variable();
+        if let Some(value) = (self.variable)(&variable).filter(|value| !value.is_empty()) {
+            setting.check(&value, &format!("${}", variable))?;
+            return Ok(Some(value));
+        }
+        if let Some(value) = self.file_value(name) {
+            setting.check(&value, &self.path.display().to_string())?;
+            return Ok(Some(value));
+        }
+        Ok(Some(setting.default.to_string()).filter(|value| !value.is_empty()))
+    }
+
+    /// Change a setting or add an alias in the file
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), Error> {
+        let (section, key) = name.split_once('.').unwrap_or((name, ""));
+        let value = if section == ALIASES {
+            if key.is_empty() || is_command(key) {
+                return Err(Error::Invalid(format!("Alias \"{}\" should have a name which is not a command", name)));
+            }
+            match shlex::split(value) {
+                Some(words) if !words.is_empty() => Value::String(value.to_string()),
+                _ => return Err(Error::Invalid(format!("Alias \"{}\" should be a command, not \"{}\"", key, value))),
+            }
+        } else {
+            let setting = Setting::find(name)?;
+            setting.check(value, "the command line")?;
+            match setting.kind {
+                Kind::Flag => Value::Boolean(value == "true"),
+                _ => Value::String(value.to_string()),
+            }
+        };
+        let section = self.table.entry(section.to_string()).or_insert_with(|| Value::Table(Table::new()));
+        match section {
+            Value::Table(section) => section.insert(key.to_string(), value),
+            _ => return Err(Error::Parse(format!("Section of \"{}\" in {} is not a table", name, self.path.display()))),
+        };
+        self.save()
+    }
+
+    /// Remove a setting or an alias from the file, so that it has its default value again
+    pub fn unset(&mut self, name: &str) -> Result<(), Error> {
+        if !name.starts_with(&format!("{}.", ALIASES)) {
+            Setting::find(name)?;
+        }
+        let (section, key) = name.split_once('.').unwrap_or((name, ""));
+        let removed = match self.table.get_mut(section) {
+            Some(Value::Table(table)) => table.remove(key).is_some(),
+            _ => false,
+        };
+        if !removed {
+            return Err(Error::NotFound(format!("Setting \"{}\" is not set in {}", name, self.path.display())));
+        }
+        self.save()
+    }
+
+    /// Every setting with its current value, empty if it has none, followed by aliases
+    pub fn list(&self) -> Result<Vec<(String, String)>, Error> {
+        let mut settings = Vec::new();
+        for setting in SETTINGS {
+            settings.push((setting.name.to_string(), self.get(setting.name)?.unwrap_or_default()));
+        }
+        if let Some(Value::Table(aliases)) = self.table.get(ALIASES) {
+            for name in aliases.keys() {
+                let name = format!("{}.{}", ALIASES, name);
+                settings.push((name.to_string(), self.file_value(&name).unwrap_or_default()));
+            }
+        }
+        Ok(settings)
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, Error> {
+        Ok(self.get(name)?.as_deref() == Some("true"))
+    }
+
+    /// Directory with records, metadata, history and logs of jobs. `~` stands for the home directory
+    pub fn store_path(&self) -> Result<PathBuf, Error> {
+        let path = self.get("store.path")?.unwrap_or_default();
+        Ok(match path.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => home_dir()?.join(rest.trim_start_matches('/')),
+            _ => PathBuf::from(path),
+        })
+    }
+
+    /// Storage of records, other backends can be added as choices of `store.backend`
+    pub fn backend(&self) -> Result<Backend, Error> {
+        match self.get("store.backend")?.as_deref() {
+            Some("file") | None => Ok(Backend::File),
+            Some(other) => Err(Error::Invalid(format!("Backend \"{}\" is not supported", other))),
+        }
+    }
+
+    /// Whether output may be coloured, by default only when it is shown in a terminal
+    pub fn use_color(&self) -> Result<bool, Error> {
+        match self.get("output.color")?.as_deref() {
+            Some("always") => Ok(true),
+            Some("never") => Ok(false),
+            _ => Ok(is_interactive()),
+        }
+    }
+
+    /// Whether the user should confirm an action, e.g. "remove", "clear" or "exec"
+    pub fn confirm(&self, action: &str) -> Result<bool, Error> {
+        self.flag(&format!("confirm.{}", action))
+    }
+
+    /// Arguments replacing alias of a subcommand, `None` if there is no such alias
+    pub fn alias(&self, name: &str) -> Result<Option<Vec<String>>, Error> {
+        match self.file_value(&format!("{}.{}", ALIASES, name)) {
+            Some(command) => shlex::split(&command)
+                .filter(|words| !words.is_empty())
+                .map(Some)
+                .ok_or_else(|| Error::Parse(format!("Alias \"{}\" could not be parsed: {}", name, command))),
+            None => Ok(None),
+        }
+    }
+}
+
+pub fn home_dir() -> Result<PathBuf, Error> {
+    dirs::home_dir().ok_or_else(|| Error::Storage("Could not determine user home directory".to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config(content: &str) -> Config {
+        let path = env::temp_dir().join(format!("dumpb-config-{}-{}.toml", std::process::id(), content.len()));
+        Config { path, table: toml::from_str(content).unwrap(), variable: |_| None }
+    }
+
+    #[test]
+    fn values_come_from_file_or_defaults() {
+        let config = config("[output]\nformat = \"table\"\n[confirm]\nremove = true\n");
+        assert_eq!(config.get("output.format").unwrap(), Some("table".to_string()));
+        assert_eq!(config.get("output.color").unwrap(), Some("auto".to_string()));
+        assert_eq!(config.backend().unwrap(), Backend::File);
+        assert_eq!(config.get("exec.shell").unwrap(), None);
+        assert!(config.confirm("remove").unwrap());
+        assert!(!config.confirm("clear").unwrap());
+        assert!(config.get("output.formats").is_err());
+    }
+
+    #[test]
+    fn environment_takes_precedence_over_file() {
+        let mut config = config("[output]
+format = \"table\"\ncolor = \"never\"\n");
+        config.variable = |name| match name {
+            "DUMPB_OUTPUT_FORMAT" => Some("yaml".to_string()),
+            "DUMPB_OUTPUT_COLOR" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(config.get("output.format").unwrap(), Some("yaml".to_string()));
+        assert_eq!(config.get("output.color").unwrap(), Some("never".to_string()));
+        config.variable = |_| Some("sometimes".to_string());
+        assert!(matches!(config.get("output.color"), Err(Error::Invalid(message)) if message.contains("$DUMPB_OUTPUT_COLOR")));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let config = config("[output]\ncolor = \"sometimes\"\n[store]\nbackend = \"memory\"\n");
+        assert!(matches!(config.get("output.color"), Err(Error::Invalid(message)) if message.contains("auto, always, never")));
+        assert!(config.backend().is_err());
+        assert_eq!(Setting::find("output.color").unwrap().variable(), "DUMPB_OUTPUT_COLOR");
+    }
+
+    #[test]
+    fn settings_and_aliases_are_saved() {
+        let mut config = config("");
+        config.set("confirm.exec", "true").unwrap();
+        config.set("aliases.ls", "list --format table").unwrap();
+        assert!(config.set("aliases.get", "list").is_err());
+        assert!(config.set("confirm.exec", "yes").is_err());
+
+        let saved = Config::read(&config.path, |_| None).unwrap();
+        fs::remove_file(&config.path).ok();
+        assert_eq!(saved.table["confirm"]["exec"], Value::Boolean(true));
+        assert_eq!(saved.alias("ls").unwrap(), Some(vec!["list".to_string(), "--format".to_string(), "table".to_string()]));
+        assert_eq!(saved.alias("g").unwrap(), None);
+        assert!(saved.list().unwrap().contains(&("aliases.ls".to_string(), "list --format table".to_string())));
+    }
+}
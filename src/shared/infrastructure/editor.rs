@@ -0,0 +1,58 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::temp_file::{create_file, temp_path};
+
+/// Editor used when neither $VISUAL nor $EDITOR is set
+static DEFAULT_EDITOR: &str = "vi";
+
+/// Program and arguments of the editor, e.g. "code --wait" becomes ["code", "--wait"]
+fn editor_command(visual: Option<String>, editor: Option<String>) -> Result<Vec<String>, Error> {
+    let is_set = |command: &String| !command.trim().is_empty();
+    let command = visual.filter(is_set).or_else(|| editor.filter(is_set));
+    let command = command.unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    match shlex::split(&command) {
+        Some(words) if !words.is_empty() => Ok(words),
+        _ => Err(Error::Parse(format!("Editor \"{}\" could not be parsed", command))),
+    }
+}
+
+/// Let the user change text in the configured editor, or the one from $VISUAL or $EDITOR.
+/// Editors usually add a final newline, which is dropped if the original text had none
+pub fn edit_text(text: &str, configured: Option<&str>) -> Result<String, Error> {
+    let visual = configured.map(str::to_string).or_else(|| env::var("VISUAL").ok());
+    let words = editor_command(visual, env::var("EDITOR").ok())?;
+    // Value may contain secrets, so only the user can read the file
+    let path = temp_path("edit", ".txt")?;
+    create_file(&path, text, 0o600)?;
+    let status = Command::new(&words[0]).args(&words[1..]).arg(&path).status();
+    let edited = fs::read_to_string(&path).map_err(|e| Error::Storage(format!("Could not read {}: {}", path.display(), e)));
+    fs::remove_file(&path).ok();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => return Err(Error::Exec(format!("Editor \"{}\" failed with {}, nothing was changed", words[0], status))),
+        Err(e) => return Err(Error::Exec(format!("Could not start editor \"{}\": {}", words[0], e))),
+    }
+    let edited = edited?;
+    match edited.strip_suffix('\n') {
+        Some(stripped) if !text.ends_with('\n') => Ok(stripped.to_string()),
+        _ => Ok(edited),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn editor_is_taken_from_visual_then_editor() {
+        let visual = Some("code --wait".to_string());
+        let editor = Some("nano".to_string());
+        assert_eq!(editor_command(visual, editor.clone()), Ok(vec!["code".to_string(), "--wait".to_string()]));
+        assert_eq!(editor_command(Some(" ".to_string()), editor), Ok(vec!["nano".to_string()]));
+        assert_eq!(editor_command(None, None), Ok(vec!["vi".to_string()]));
+        assert!(editor_command(Some("vim '".to_string()), None).is_err());
+    }
+}
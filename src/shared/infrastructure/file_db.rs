@@ -1,49 +1,68 @@
 #![allow(dead_code)]
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
 use regex::Regex;
 
+use crate::shared::domain::error::Error;
+
 static KV_SPLIT: &'static str = "|>!<|";
 static LINE_TERM: &'static str = "|<!>|\n";
-static LINE_REGEX: &'static str = r"^(.+)\|>!<\|(?s)(.+)\|<!>\|\n$"; 
+static LINE_REGEX: &'static str = r"^(.+?)\|>!<\|(?s)(.+)\|<!>\|\n$";
 
 pub struct KVFileDatabase {
     location: PathBuf,
     data: RefCell<HashMap<String, String>>,
+    /// Whether data was changed since it was read, only then file is written
+    changed: Cell<bool>,
 }
 
 impl KVFileDatabase {
-    pub fn new(location: &PathBuf) -> Result<Self, String> {
+    pub fn new(location: &PathBuf) -> Result<Self, Error> {
         let file_content = Self::read_file(&location)?;
         let parsed_data = Self::parse_content(&file_content)?;
         Ok(KVFileDatabase {
             location: location.clone(),
             data: RefCell::new(parsed_data),
+            changed: Cell::new(false),
         })
     }
 
-    pub fn add(&self, key: &String, value: &String) -> Result<(), String> {
+    pub fn add(&self, key: &String, value: &String) -> Result<(), Error> {
+        Self::check_entry(key, value)?;
         let mut storage = self.data.borrow_mut();
         if storage.contains_key(key) {
-            Err(format!("Key \"{}\" already exists", key))
+            Err(Error::AlreadyExists(format!("Key \"{}\" already exists", key)))
         } else {
             storage.insert(key.to_string(), value.to_string());
+            self.changed.set(true);
             Ok(())
         }
     }
 
-    pub fn get(&self, key: &String) -> Result<String, String> {
+    /// Insert value or replace existing one
+    pub fn set(&self, key: &str, value: &str) -> Result<(), Error> {
+        Self::check_entry(key, value)?;
+        self.data.borrow_mut().insert(key.to_string(), value.to_string());
+        self.changed.set(true);
+        Ok(())
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.data.borrow().contains_key(key)
+    }
+
+    pub fn get(&self, key: &String) -> Result<String, Error> {
         match self.data.borrow().get(key) {
             Some(value) => Ok(value.to_string()),
-            None => Err(format!("Key \"{}\" does not exist", key)),
+            None => Err(Error::NotFound(format!("Key \"{}\" does not exist", key))),
         }
     }
 
-    pub fn items(&self) -> Result<Vec<(String, String)>, String> {
+    pub fn items(&self) -> Result<Vec<(String, String)>, Error> {
         Ok(self.data
             .borrow()
             .iter()
@@ -52,15 +71,44 @@ impl KVFileDatabase {
         )
     }
 
-    pub fn remove(&self, key: &String) -> Result<(), String> {
+    pub fn remove(&self, key: &String) -> Result<(), Error> {
         match self.data.borrow_mut().remove(key) {
-            Some(_) => Ok(()),
-            None => Err(format!("Key \"{}\" does not exist", key)),
+            Some(_) => {
+                self.changed.set(true);
+                Ok(())
+            }
+            None => Err(Error::NotFound(format!("Key \"{}\" does not exist", key))),
         }
     }
 
     pub fn clear(&self) {
-        self.data.borrow_mut().clear()
+        self.data.borrow_mut().clear();
+        self.changed.set(true);
+    }
+
+    /// Make sure entry can be written into file and read back unchanged
+    fn check_entry(key: &str, value: &str) -> Result<(), Error> {
+        if key.is_empty() || key.contains('\n') || key.contains(KV_SPLIT) {
+            return Err(Error::Invalid(format!("Key \"{}\" can not be stored", key)));
+        }
+        if value.is_empty() {
+            return Err(Error::Invalid(format!("Value of key \"{}\" is empty", key)));
+        }
+        if value.contains(LINE_TERM) {
+            return Err(Error::Invalid(format!("Value of key \"{}\" contains reserved sequence {:?}", key, LINE_TERM)));
+        }
+        Ok(())
+    }
+
+    /// Write updated hashmap to file storage, unchanged data is not written again
+    pub fn save(&self) -> Result<(), Error> {
+        if !self.changed.get() {
+            return Ok(());
+        }
+        fs::write(&self.location, self.construct_dump())
+            .map_err(|e| Error::Storage(format!("Could not write {}: {}", self.location.display(), e)))?;
+        self.changed.set(false);
+        Ok(())
     }
 
     fn construct_dump(&self) -> String {
@@ -72,37 +120,39 @@ impl KVFileDatabase {
             .join("")
     }
 
-    fn read_file(location: &PathBuf) -> Result<String, String> {
+    fn read_file(location: &PathBuf) -> Result<String, Error> {
         if location.is_file() {
             // DB file already exists, reading its content
             Ok(fs::read_to_string(location).unwrap())
         } else if location.exists() {
             // Location specifies not valid file (maybe it is a directory)
-            Err("Object specified by path exists, but it is not a valid file".to_string())
+            Err(Error::Storage("Object specified by path exists, but it is not a valid file".to_string()))
         } else {
             // File does not exist yet, consider as empty
             Ok(String::new())
         }
     }
 
-    fn parse_content(content: &String) -> Result<HashMap<String, String>, String> {
+    fn parse_content(content: &String) -> Result<HashMap<String, String>, Error> {
         let line_regex = Regex::new(LINE_REGEX).unwrap();
         let lines = content.split_inclusive(LINE_TERM);
-        lines.map(|line| -> Result<(String, String), String> {
+        lines.map(|line| -> Result<(String, String), Error> {
             match line_regex.captures(line) {
                 Some(groups) if groups.len() == 3 => {
                     Ok((groups[1].to_string(), groups[2].to_string()))
                 },
-                _ => Err(format!("Line \"{}\" has incompatible format", line)),
+                _ => Err(Error::Storage(format!("Line \"{}\" has incompatible format", line))),
             }
         }).collect()
     }
 }
 
 impl Drop for KVFileDatabase {
-    /// Save updated hashmap to file storage
+    /// Save data which was not saved explicitly, errors can only be shown at this point
     fn drop(&mut self) {
-        fs::write(&self.location, self.construct_dump()).unwrap();
+        if let Err(e) = self.save() {
+            eprintln!("[ERROR]: {}", e);
+        }
     }
 }
 
@@ -149,4 +199,33 @@ mod test {
         assert!(KVFileDatabase::parse_content(&bad_line_sep).is_err());
         assert!(KVFileDatabase::parse_content(&bad_seps).is_err());
     }
+
+    #[test]
+    fn value_may_contain_key_separator() {
+        let line = format!("key{}left{}right\n{}", KV_SPLIT, KV_SPLIT, LINE_TERM);
+        assert_eq!(
+            KVFileDatabase::parse_content(&line),
+            Ok(hashmap!["key".to_string() => format!("left{}right\n", KV_SPLIT)])
+        );
+    }
+
+    #[test]
+    fn rejects_entries_which_can_not_be_read_back() {
+        assert!(KVFileDatabase::check_entry("key", "multi\nline\n").is_ok());
+        assert!(KVFileDatabase::check_entry("key", "").is_err());
+        assert!(KVFileDatabase::check_entry("key", &format!("value{}", LINE_TERM)).is_err());
+        assert!(KVFileDatabase::check_entry(&format!("a{}b", KV_SPLIT), "value").is_err());
+        assert!(KVFileDatabase::check_entry("a\nb", "value").is_err());
+    }
+
+    #[test]
+    fn failed_save_is_reported() {
+        let location = std::env::temp_dir().join(format!("dumpb-missing-{}", std::process::id())).join("store");
+        let db = KVFileDatabase::new(&location).unwrap();
+        assert_eq!(db.save(), Ok(()));
+        db.add(&"key".to_string(), &"value".to_string()).unwrap();
+        assert!(matches!(db.save(), Err(Error::Storage(message)) if message.contains("Could not write")));
+        // Nothing is left to save when dropped
+        db.changed.set(false);
+    }
 }
@@ -1,2 +1,11 @@
 pub mod cli;
-pub mod file_db;
\ No newline at end of file
+pub mod completions;
+pub mod config;
+pub mod editor;
+pub mod file_db;
+pub mod picker;
+pub mod prompt;
+pub mod shell_history;
+pub mod temp_file;
+pub mod terminal;
+pub mod tui;
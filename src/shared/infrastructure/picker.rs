@@ -0,0 +1,218 @@
+use std::io::Write;
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::queue;
+use crossterm::terminal::{self, Clear, ClearType};
+
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::terminal::FullScreen;
+
+/// Lines taken by query, counter and separator of preview
+const CHROME_LINES: u16 = 3;
+
+/// Item shown in picker
+pub struct PickerItem {
+    /// Single line shown in the list
+    pub label: String,
+    /// Text matched against the query
+    pub text: String,
+    /// Text shown under the list while item is selected
+    pub preview: String,
+}
+
+/// Items matching a query ordered by score, with selection and scrolling, shared by picker and browser
+#[derive(Debug, Default)]
+pub struct FilteredList {
+    /// Indexes of items matching the query, the best match first
+    pub matches: Vec<usize>,
+    pub selected: usize,
+    /// Index of the first match shown in the list
+    pub offset: usize,
+}
+
+impl FilteredList {
+    /// Keep items which have a score, in order of `scores`. Items with equal score keep their order,
+    /// selection stays within matches
+    pub fn filter(&mut self, scores: impl Iterator<Item = Option<i64>>) {
+        let mut scored: Vec<(i64, usize)> =
+            scores.enumerate().filter_map(|(index, score)| score.map(|score| (-score, index))).collect();
+        scored.sort();
+        self.matches = scored.into_iter().map(|(_, index)| index).collect();
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let last = self.matches.len() as isize - 1;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+
+    /// Scroll the list of given height to keep selected item visible
+    pub fn scroll(&mut self, height: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + height {
+            self.offset = self.selected + 1 - height;
+        }
+    }
+
+    /// Index of the selected item, `None` if nothing matches
+    pub fn selected_item(&self) -> Option<usize> {
+        self.matches.get(self.selected).copied()
+    }
+}
+
+/// Query and selection of picker, kept apart from the terminal
+struct PickerState<'a> {
+    items: &'a [PickerItem],
+    score: &'a dyn Fn(&str, &str) -> Option<i64>,
+    query: String,
+    list: FilteredList,
+}
+
+impl<'a> PickerState<'a> {
+    fn new(items: &'a [PickerItem], query: &str, score: &'a dyn Fn(&str, &str) -> Option<i64>) -> PickerState<'a> {
+        let mut state = PickerState { items, score, query: query.to_string(), list: FilteredList::default() };
+        state.filter();
+        state
+    }
+
+    /// Best match is selected after every change of the query
+    fn filter(&mut self) {
+        let (query, score) = (&self.query, self.score);
+        self.list.filter(self.items.iter().map(|item| score(query, &item.text)));
+        self.list.selected = 0;
+        self.list.offset = 0;
+    }
+
+    fn edit(&mut self, change: impl FnOnce(&mut String)) {
+        change(&mut self.query);
+        self.filter();
+    }
+
+    fn picked(&self) -> Option<usize> {
+        self.list.selected_item()
+    }
+}
+
+fn draw(screen: &mut FullScreen, state: &mut PickerState) -> std::io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let width = width as usize;
+    let list_height = (height.saturating_sub(CHROME_LINES) / 2).max(1);
+    state.list.scroll(list_height as usize);
+    let line = |text: &str| -> String { text.replace('\t', "    ").chars().take(width).collect() };
+
+    queue!(screen, Clear(ClearType::All), MoveTo(0, 0), Print(line(&format!("> {}", state.query))))?;
+    let counter = format!("  {}/{}", state.list.matches.len(), state.items.len());
+    queue!(screen, MoveTo(0, 1), SetAttribute(Attribute::Dim), Print(line(&counter)), SetAttribute(Attribute::Reset))?;
+    let visible = state.list.matches.iter().enumerate().skip(state.list.offset).take(list_height as usize);
+    for (row, (position, index)) in visible.enumerate() {
+        let y = 2 + row as u16;
+        if position == state.list.selected {
+            let label = line(&format!("> {}", state.items[*index].label));
+            queue!(screen, MoveTo(0, y), SetAttribute(Attribute::Reverse), Print(label), SetAttribute(Attribute::Reset))?;
+        } else {
+            queue!(screen, MoveTo(0, y), Print(line(&format!("  {}", state.items[*index].label))))?;
+        }
+    }
+    let separator_y = 2 + list_height;
+    queue!(screen, MoveTo(0, separator_y), Print("─".repeat(width)))?;
+    if let Some(index) = state.picked() {
+        let preview_lines = state.items[index].preview.lines().take(height.saturating_sub(separator_y + 1) as usize);
+        for (row, text) in preview_lines.enumerate() {
+            queue!(screen, MoveTo(0, separator_y + 1 + row as u16), Print(line(text)))?;
+        }
+    }
+    screen.flush()
+}
+
+/// Let the user choose an item in the terminal, typing filters items by `score`.
+/// Returns index of the chosen item, or `None` if picking was cancelled
+pub fn pick(items: &[PickerItem], query: &str, score: &dyn Fn(&str, &str) -> Option<i64>) -> Result<Option<usize>, Error> {
+    if items.is_empty() {
+        return Err(Error::NotFound("There are no records to pick from".to_string()));
+    }
+    let mut state = PickerState::new(items, query, score);
+    let mut screen = FullScreen::enter()?;
+    loop {
+        draw(&mut screen, &mut state).map_err(|e| Error::Exec(e.to_string()))?;
+        let page = (terminal::size().map_or(0, |(_, height)| height).saturating_sub(CHROME_LINES) / 2).max(1) as isize;
+        let key = match event::read().map_err(|e| Error::Exec(e.to_string()))? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key {
+            KeyEvent { code: KeyCode::Esc, .. } => return Ok(None),
+            KeyEvent { code: KeyCode::Char('c'), .. } | KeyEvent { code: KeyCode::Char('g'), .. } if control => {
+                return Ok(None)
+            }
+            KeyEvent { code: KeyCode::Enter, .. } => return Ok(state.picked()),
+            KeyEvent { code: KeyCode::Up, .. } => state.list.move_selection(-1),
+            KeyEvent { code: KeyCode::Down, .. } => state.list.move_selection(1),
+            KeyEvent { code: KeyCode::Char('p'), .. } | KeyEvent { code: KeyCode::Char('k'), .. } if control => {
+                state.list.move_selection(-1)
+            }
+            KeyEvent { code: KeyCode::Char('n'), .. } | KeyEvent { code: KeyCode::Char('j'), .. } if control => {
+                state.list.move_selection(1)
+            }
+            KeyEvent { code: KeyCode::PageUp, .. } => state.list.move_selection(-page),
+            KeyEvent { code: KeyCode::PageDown, .. } => state.list.move_selection(page),
+            KeyEvent { code: KeyCode::Backspace, .. } => state.edit(|query| {
+                query.pop();
+            }),
+            KeyEvent { code: KeyCode::Char('u'), .. } if control => state.edit(String::clear),
+            KeyEvent { code: KeyCode::Char('w'), .. } if control => state.edit(|query| {
+                let kept = query.trim_end().rfind(' ').map_or(0, |space| space + 1);
+                query.truncate(kept);
+            }),
+            KeyEvent { code: KeyCode::Char(c), .. } if !control => state.edit(|query| query.push(c)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn items() -> Vec<PickerItem> {
+        ["docker compose up", "git log", "docker ps"]
+            .iter()
+            .map(|text| PickerItem { label: text.to_string(), text: text.to_string(), preview: String::new() })
+            .collect()
+    }
+
+    fn contains(query: &str, text: &str) -> Option<i64> {
+        text.find(query).map(|position| -(position as i64))
+    }
+
+    #[test]
+    fn query_filters_and_orders_items() {
+        let items = items();
+        let mut state = PickerState::new(&items, "", &contains);
+        assert_eq!(state.list.matches, vec![0, 1, 2]);
+        state.edit(|query| query.push_str("ps"));
+        assert_eq!(state.list.matches, vec![2]);
+        state.edit(|query| *query = "o".to_string());
+        assert_eq!(state.list.matches, vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn selection_stays_within_matches_and_visible() {
+        let items = items();
+        let mut state = PickerState::new(&items, "", &contains);
+        state.list.move_selection(-1);
+        assert_eq!(state.picked(), Some(0));
+        state.list.move_selection(10);
+        assert_eq!(state.picked(), Some(2));
+        state.list.scroll(2);
+        assert_eq!(state.list.offset, 1);
+        state.edit(|query| query.push('x'));
+        assert_eq!(state.picked(), None);
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::{self, BufRead, BufReader, Write};
+
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::config::Config;
+use crate::shared::infrastructure::terminal::open_terminal;
+
+/// Print question and read a line of answer from stdin
+pub fn ask(question: &str) -> Result<String, Error> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", question).and_then(|_| stdout.flush()).map_err(|e| Error::Exec(e.to_string()))?;
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .map_err(|e| Error::Exec(format!("Could not read answer: {}", e)))?;
+    Ok(answer.trim().to_string())
+}
+
+/// Ask the user to go on if the configuration requires confirmation of the action
+pub fn confirmed(config: &Config, action: &str, question: &str) -> Result<bool, Error> {
+    if !config.confirm(action)? {
+        return Ok(true);
+    }
+    let answer = ask(&format!("{} [y/N]: ", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Print question and read a line of answer on the terminal, when stdout is captured by shell
+pub fn ask_terminal(question: &str) -> Result<String, Error> {
+    let mut tty = open_terminal()?;
+    write!(tty, "{}", question).and_then(|_| tty.flush()).map_err(|e| Error::Exec(e.to_string()))?;
+    let mut answer = String::new();
+    BufReader::new(tty)
+        .read_line(&mut answer)
+        .map_err(|e| Error::Exec(format!("Could not read answer: {}", e)))?;
+    Ok(answer.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Parse 1-based numbers and ranges like "1 3, 5-7" into 0-based indexes of `count` items
+pub fn parse_selection(selection: &str, count: usize) -> Result<Vec<usize>, Error> {
+    let mut indexes = Vec::new();
+    let number = |raw: &str| -> Result<usize, Error> {
+        match raw.trim().parse::<usize>() {
+            Ok(n) if n >= 1 && n <= count => Ok(n - 1),
+            _ => Err(Error::Invalid(format!("\"{}\" is not a number between 1 and {}", raw, count))),
+        }
+    };
+    for part in selection.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (number(from)?, number(to)?);
+                if from > to {
+                    return Err(Error::Invalid(format!("Range \"{}\" should go from the lower number to the higher", part)));
+                }
+                indexes.extend(from..=to);
+            }
+            None => indexes.push(number(part)?),
+        }
+    }
+    let mut seen = Vec::new();
+    indexes.retain(|index| {
+        let first = !seen.contains(index);
+        seen.push(*index);
+        first
+    });
+    Ok(indexes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn selection_accepts_numbers_and_ranges() {
+        assert_eq!(parse_selection("1 3, 5-7", 10), Ok(vec![0, 2, 4, 5, 6]));
+        assert_eq!(parse_selection("2 2 1-2", 10), Ok(vec![1, 0]));
+        assert_eq!(parse_selection("", 10), Ok(vec![]));
+        assert_eq!(parse_selection("5-5", 10), Ok(vec![4]));
+        assert!(matches!(parse_selection("7-5", 10), Err(Error::Invalid(message)) if message.contains("7-5")));
+    }
+
+    #[test]
+    fn selection_rejects_numbers_out_of_range() {
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("one", 3).is_err());
+    }
+}
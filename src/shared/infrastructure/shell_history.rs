@@ -0,0 +1,232 @@
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::os::unix::process::parent_id;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::shared::domain::error::Error;
+
+/// Name of this program, its own invocations are skipped when looking for commands
+static PROGRAM_NAME: &str = "dumpb";
+/// Zsh writes special bytes into history file prefixed with this one
+const ZSH_META: u8 = 0x83;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    fn from_name(name: &str) -> Option<Shell> {
+        // Login shells are reported with leading dash, e.g. "-zsh"
+        match name.trim().trim_start_matches('-').rsplit('/').next()? {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            _ => None,
+        }
+    }
+
+    /// Shell which started this program, falling back to the login shell
+    pub fn detect() -> Result<Shell, Error> {
+        let parent = fs::read_to_string(format!("/proc/{}/comm", parent_id())).unwrap_or_default();
+        Shell::from_name(&parent)
+            .or_else(|| env::var("SHELL").ok().and_then(|shell| Shell::from_name(&shell)))
+            .ok_or_else(|| Error::Invalid("Could not determine shell, only bash, zsh and fish are supported".to_string()))
+    }
+
+    /// Default location of the history file, $HISTFILE takes precedence for bash and zsh
+    fn history_location(&self, home: &Path) -> PathBuf {
+        let histfile = env::var_os("HISTFILE").filter(|_| *self != Shell::Fish);
+        match (self, histfile) {
+            (_, Some(histfile)) => PathBuf::from(histfile),
+            (Shell::Bash, None) => home.join(".bash_history"),
+            (Shell::Zsh, None) => env::var_os("ZDOTDIR").map_or(home.to_path_buf(), PathBuf::from).join(".zsh_history"),
+            (Shell::Fish, None) => env::var_os("XDG_DATA_HOME")
+                .map_or(home.join(".local/share"), PathBuf::from)
+                .join("fish/fish_history"),
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Shell::from_name(name).ok_or(format!("Shell \"{}\" is not supported", name))
+    }
+}
+
+/// Commands read from history file of a shell
+pub struct ShellHistory {
+    shell: Shell,
+    location: PathBuf,
+}
+
+impl ShellHistory {
+    pub fn new(shell: Shell, location: &Path) -> ShellHistory {
+        ShellHistory { shell, location: location.to_path_buf() }
+    }
+
+    /// History of the shell which started this program
+    pub fn detect(home: &Path) -> Result<ShellHistory, Error> {
+        ShellHistory::locate(None, None, home)
+    }
+
+    /// History of given shell, or the detected one, read from its default location unless other is given
+    pub fn locate(shell: Option<Shell>, location: Option<&Path>, home: &Path) -> Result<ShellHistory, Error> {
+        let shell = match shell {
+            Some(shell) => shell,
+            None => Shell::detect()?,
+        };
+        let location = location.map_or_else(|| shell.history_location(home), Path::to_path_buf);
+        Ok(ShellHistory::new(shell, &location))
+    }
+
+    /// All commands, oldest first. Invocations of this program are skipped
+    pub fn commands(&self) -> Result<Vec<String>, Error> {
+        let content = fs::read(&self.location)
+            .map_err(|e| Error::Storage(format!("Could not read shell history {}: {}", self.location.display(), e)))?;
+        let commands = match self.shell {
+            Shell::Bash => parse_bash(&String::from_utf8_lossy(&content)),
+            Shell::Zsh => parse_zsh(&String::from_utf8_lossy(&unmetafy(&content))),
+            Shell::Fish => parse_fish(&String::from_utf8_lossy(&content)),
+        };
+        Ok(commands.into_iter().filter(|command| !is_own_invocation(command)).collect())
+    }
+
+    /// The most recent command
+    pub fn last_command(&self) -> Result<String, Error> {
+        self.commands()?.pop().ok_or_else(|| Error::NotFound("Shell history has no commands".to_string()))
+    }
+}
+
+fn is_own_invocation(command: &str) -> bool {
+    match command.split_whitespace().next() {
+        Some(program) => program.rsplit('/').next() == Some(PROGRAM_NAME),
+        None => true,
+    }
+}
+
+/// Bash history has one command per line, optionally preceded by "#<timestamp>" line
+fn parse_bash(content: &str) -> Vec<String> {
+    let timestamp = Regex::new(r"^#\d+$").unwrap();
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !timestamp.is_match(line))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Zsh history has either plain lines or extended ": <start>:<duration>;<command>" ones.
+/// Lines of multi-line commands end with backslash
+fn parse_zsh(content: &str) -> Vec<String> {
+    let extended = Regex::new(r"^: *\d+:\d+;").unwrap();
+    let mut commands = Vec::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        let line = match current.take() {
+            Some(previous) => format!("{}\n{}", previous, line),
+            None => extended.replace(line, "").to_string(),
+        };
+        match line.strip_suffix('\\') {
+            Some(continued) => current = Some(continued.to_string()),
+            None => commands.push(line),
+        }
+    }
+    commands.extend(current);
+    commands.into_iter().filter(|command| !command.trim().is_empty()).collect()
+}
+
+/// Restore bytes which zsh escaped with meta byte
+fn unmetafy(content: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(content.len());
+    let mut bytes = content.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            ZSH_META => result.extend(bytes.next().map(|next| next ^ 32)),
+            _ => result.push(byte),
+        }
+    }
+    result
+}
+
+/// Fish history is YAML-like, command is stored in "- cmd: " line with escaped backslashes and newlines
+fn parse_fish(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| line.strip_prefix("- cmd: "))
+        .map(|command| {
+            let mut unescaped = String::new();
+            let mut chars = command.chars();
+            while let Some(c) = chars.next() {
+                match (c, chars.clone().next()) {
+                    ('\\', Some('n')) => {
+                        unescaped.push('\n');
+                        chars.next();
+                    }
+                    ('\\', Some('\\')) => {
+                        unescaped.push('\\');
+                        chars.next();
+                    }
+                    _ => unescaped.push(c),
+                }
+            }
+            unescaped
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_bash_history_with_timestamps() {
+        let content = "ls -la\n#1634567890\ngit status\n\n";
+        assert_eq!(parse_bash(content), vec!["ls -la".to_string(), "git status".to_string()]);
+    }
+
+    #[test]
+    fn parses_extended_and_multiline_zsh_history() {
+        let content = ": 1634567890:0;ls -la\n: 1634567891:2;for f in *; do\\\n  echo $f\\\ndone\nplain\n";
+        assert_eq!(
+            parse_zsh(content),
+            vec!["ls -la".to_string(), "for f in *; do\n  echo $f\ndone".to_string(), "plain".to_string()]
+        );
+    }
+
+    #[test]
+    fn restores_metafied_zsh_bytes() {
+        let content = [b'e', b'c', b'h', b'o', b' ', 0xd0, ZSH_META, 0xb0 ^ 32];
+        assert_eq!(String::from_utf8(unmetafy(&content)).unwrap(), "echo \u{430}");
+    }
+
+    #[test]
+    fn parses_fish_history() {
+        let content = "- cmd: echo one\\ntwo\n  when: 1634567890\n  paths:\n    - two\n- cmd: printf \\\\t\n  when: 1634567891\n";
+        assert_eq!(parse_fish(content), vec!["echo one\ntwo".to_string(), "printf \\t".to_string()]);
+    }
+
+    #[test]
+    fn last_command_skips_own_invocations() {
+        let location = env::temp_dir().join(format!("dumpb-shell-history-{}", std::process::id()));
+        fs::write(&location, "cargo build\n/usr/bin/dumpb add build --last\ndumpb save-last build\n").unwrap();
+        let history = ShellHistory::new(Shell::Bash, &location);
+        assert_eq!(history.last_command(), Ok("cargo build".to_string()));
+        fs::remove_file(&location).ok();
+    }
+
+    #[test]
+    fn shell_is_recognized_by_name_or_path() {
+        assert_eq!(Shell::from_name("-zsh\n"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_name("/usr/bin/fish"), Some(Shell::Fish));
+        assert_eq!(Shell::from_name("tcsh"), None);
+    }
+}
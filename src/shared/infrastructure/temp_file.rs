@@ -0,0 +1,68 @@
+use std::env;
+use std::fs::{self, DirBuilder, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::shared::domain::error::Error;
+
+/// Directory in the system temporary directory only the current user can access, so that other users
+/// can neither read temporary files nor put files or symlinks in their place
+fn private_dir() -> Result<PathBuf, Error> {
+    let uid = unsafe { libc::getuid() };
+    let dir = env::temp_dir().join(format!("dumpb-{}", uid));
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => return Ok(dir),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(Error::Storage(format!("Could not create {}: {}", dir.display(), e))),
+    }
+    // Directory may have been created by somebody else before, it is used only if it is safe
+    let metadata = fs::symlink_metadata(&dir).map_err(|e| Error::Storage(format!("Could not check {}: {}", dir.display(), e)))?;
+    if !metadata.is_dir() || metadata.uid() != uid || metadata.mode() & 0o077 != 0 {
+        return Err(Error::Storage(format!("{} should be a directory accessible only by its owner", dir.display())));
+    }
+    Ok(dir)
+}
+
+/// Unique location of a temporary file in the private directory, e.g. `edit` and `.txt` give `edit-<pid>-<n>.txt`
+pub fn temp_path(prefix: &str, extension: &str) -> Result<PathBuf, Error> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let number = COUNTER.fetch_add(1, Ordering::SeqCst);
+    Ok(private_dir()?.join(format!("{}-{}-{}{}", prefix, process::id(), number, extension)))
+}
+
+/// Write `content` into a new file with `mode`, nothing which exists at `path` is followed or overwritten
+pub fn create_file(path: &Path, content: &str, mode: u32) -> Result<(), Error> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(mode)
+        .open(path)
+        .map_err(|e| Error::Storage(format!("Could not create {}: {}", path.display(), e)))?;
+    file.write_all(content.as_bytes()).map_err(|e| Error::Storage(format!("Could not write {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod test {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    #[test]
+    fn files_are_private_and_never_replace_existing_ones() {
+        let path = temp_path("test", ".txt").unwrap();
+        create_file(&path, "secret", 0o600).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o600);
+        assert_eq!(fs::metadata(path.parent().unwrap()).unwrap().mode() & 0o777, 0o700);
+        assert!(create_file(&path, "other", 0o600).is_err());
+
+        let link = temp_path("test", "").unwrap();
+        symlink(&path, &link).unwrap();
+        assert!(create_file(&link, "other", 0o600).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "secret");
+        fs::remove_file(&link).ok();
+        fs::remove_file(&path).ok();
+    }
+}
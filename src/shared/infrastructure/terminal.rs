@@ -0,0 +1,162 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::ops::Range;
+
+use crossterm::cursor::{Hide, Show};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+
+use crate::shared::domain::error::Error;
+
+/// Escape sequences switching bold red text on and resetting it
+const HIGHLIGHT_START: &str = "\x1b[1;31m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+/// Terminal of the user, used instead of stdout so that output of the program can be captured by shell
+static TTY: &str = "/dev/tty";
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Whether output is shown to a person, so it may contain colours
+pub fn is_interactive() -> bool {
+    io::stdout().is_terminal()
+}
+
+/// Width of the terminal output is shown in, `None` if output goes to a file or a pipe
+pub fn terminal_width() -> Option<usize> {
+    if !is_interactive() {
+        return None;
+    }
+    terminal::size().ok().map(|(width, _)| width as usize)
+}
+
+pub fn open_terminal() -> Result<File, Error> {
+    OpenOptions::new().read(true).write(true).open(TTY).map_err(|e| Error::Exec(format!("Could not open terminal: {}", e)))
+}
+
+/// Terminal in raw mode showing alternate screen, restored when dropped, even after an error
+pub struct FullScreen {
+    tty: File,
+}
+
+impl FullScreen {
+    pub fn enter() -> Result<FullScreen, Error> {
+        let mut screen = FullScreen { tty: open_terminal()? };
+        screen.resume()?;
+        Ok(screen)
+    }
+
+    /// Give the terminal back to a program running in foreground, e.g. an editor
+    pub fn suspend(&mut self) -> Result<(), Error> {
+        execute!(self.tty, Show, LeaveAlternateScreen).map_err(|e| Error::Exec(e.to_string()))?;
+        terminal::disable_raw_mode().map_err(|e| Error::Exec(format!("Could not restore terminal: {}", e)))
+    }
+
+    pub fn resume(&mut self) -> Result<(), Error> {
+        terminal::enable_raw_mode().map_err(|e| Error::Exec(format!("Could not set up terminal: {}", e)))?;
+        execute!(self.tty, EnterAlternateScreen, Hide).map_err(|e| Error::Exec(e.to_string()))
+    }
+}
+
+impl Write for FullScreen {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tty.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.tty.flush()
+    }
+}
+
+impl Drop for FullScreen {
+    fn drop(&mut self) {
+        self.suspend().ok();
+    }
+}
+
+/// Wrap given byte ranges of text with colour, ranges have to be sorted and not overlapping
+pub fn highlight(text: &str, ranges: &[Range<usize>]) -> String {
+    let mut highlighted = String::with_capacity(text.len());
+    let mut last = 0;
+    for range in ranges {
+        highlighted.push_str(&text[last..range.start]);
+        highlighted.push_str(HIGHLIGHT_START);
+        highlighted.push_str(&text[range.clone()]);
+        highlighted.push_str(HIGHLIGHT_END);
+        last = range.end;
+    }
+    highlighted.push_str(&text[last..]);
+    highlighted
+}
+
+/// Lines of text with given byte ranges of the whole text highlighted, a range spanning several lines
+/// is split so that colour is reset at the end of every line
+pub fn highlight_lines(text: &str, ranges: &[Range<usize>]) -> Vec<String> {
+    let mut offset = 0;
+    let mut lines = Vec::new();
+    for piece in text.split_inclusive('\n') {
+        let line = piece.strip_suffix('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).unwrap_or(piece);
+        let end = offset + line.len();
+        let clipped: Vec<Range<usize>> = ranges
+            .iter()
+            .map(|range| range.start.max(offset)..range.end.min(end))
+            .filter(|range| range.start < range.end)
+            .map(|range| range.start - offset..range.end - offset)
+            .collect();
+        lines.push(highlight(line, &clipped));
+        offset += piece.len();
+    }
+    lines
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let joined = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        for position in 0..4 {
+            if position <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(joined >> (18 - 6 * position) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Put text into the system clipboard with OSC 52 escape sequence, which is handled by the terminal,
+/// so it works over ssh and needs no clipboard tools
+pub fn copy_to_clipboard(text: &str) -> Result<(), Error> {
+    let mut tty = open_terminal()?;
+    write!(tty, "\x1b]52;c;{}\x07", base64(text.as_bytes())).map_err(|e| Error::Exec(format!("Could not copy into clipboard: {}", e)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ranges_are_wrapped_with_colour() {
+        assert_eq!(highlight("git log", &[0..3, 4..7]), "\x1b[1;31mgit\x1b[0m \x1b[1;31mlog\x1b[0m");
+        assert_eq!(highlight("a-a", &[0..1, 2..3]), "\x1b[1;31ma\x1b[0m-\x1b[1;31ma\x1b[0m");
+        assert_eq!(highlight("plain", &[]), "plain");
+    }
+
+    #[test]
+    fn ranges_spanning_lines_are_split() {
+        assert_eq!(
+            highlight_lines("ps\n--all\r\nls", &[0..5, 10..11]),
+            vec!["\x1b[1;31mps\x1b[0m", "\x1b[1;31m--\x1b[0mall", "\x1b[1;31ml\x1b[0ms"]
+        );
+        assert_eq!(highlight_lines("a\nb\nc\n", &[1..3, 4..5]), vec!["a", "\x1b[1;31mb\x1b[0m", "\x1b[1;31mc\x1b[0m"]);
+        assert!(highlight_lines("", &[]).is_empty());
+    }
+
+    #[test]
+    fn text_is_encoded_into_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64("ls -la\n".as_bytes()), "bHMgLWxhCg==");
+    }
+}
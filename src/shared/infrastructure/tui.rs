@@ -0,0 +1,515 @@
+use std::env;
+use std::io::Write;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType};
+
+use crate::execution::application::queries::{ExecuteRecordQuery, RunWorkflowQuery};
+use crate::execution::application::services::{ExecuteRecordService, RunWorkflowService};
+use crate::execution::domain::entities::{ExecutionLimits, HistoryEntry};
+use crate::execution::domain::repositories::HistoryRepository;
+use crate::execution::domain::runners::CommandRunner;
+use crate::record::application::queries::{AddNewRecordQuery, DeleteRecordQuery, ListRecordsQuery, UpdateRecordQuery};
+use crate::record::application::services::{
+    AddNewRecordService, ClearRecordsService, ListRecordsService, ListResult, UpdateRecordService,
+};
+use crate::record::domain::entities::Record;
+use crate::record::domain::fuzzy::score;
+use crate::record::domain::repositories::RecordRepository;
+use crate::shared::domain::error::Error;
+use crate::shared::infrastructure::config::Config;
+use crate::shared::infrastructure::editor::edit_text;
+use crate::shared::infrastructure::picker::FilteredList;
+use crate::shared::infrastructure::prompt::confirmed;
+use crate::shared::infrastructure::terminal::FullScreen;
+
+/// Keys shown in the status line when there is no message
+static HELP: &str = "↑↓ move  / filter  e edit  E editor  d delete  u undo  x run  q quit";
+
+/// Single line of text edited in place
+#[derive(Debug, Default, PartialEq)]
+struct LineInput {
+    text: Vec<char>,
+    cursor: usize,
+}
+
+impl LineInput {
+    fn new(text: &str) -> LineInput {
+        let text: Vec<char> = text.chars().collect();
+        LineInput { cursor: text.len(), text }
+    }
+
+    fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Apply editing key, returns false for keys which do not edit text
+    fn handle(&mut self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.text.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Delete if self.cursor < self.text.len() => {
+                self.text.remove(self.cursor);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Backspace | KeyCode::Delete => {}
+            _ => return false,
+        }
+        true
+    }
+}
+
+enum Mode {
+    Browse,
+    /// Typing filter, which is applied after every key
+    Filter(LineInput),
+    /// Inline editing of the value of selected record
+    Edit(LineInput),
+}
+
+/// Change made in the browser, which can be undone
+enum Change {
+    Deleted(Record),
+    /// Record as it was before editing
+    Edited(Record),
+}
+
+/// Records, filter, selection and changes of the browser, kept apart from the terminal
+struct TuiState<'a> {
+    record_repository: &'a dyn RecordRepository,
+    /// All records ordered by key
+    records: Vec<Record>,
+    filter: String,
+    /// Records matching the filter, the best match first
+    list: FilteredList,
+    mode: Mode,
+    changes: Vec<Change>,
+    message: Option<String>,
+}
+
+impl<'a> TuiState<'a> {
+    fn new(record_repository: &'a dyn RecordRepository) -> Result<TuiState<'a>, Error> {
+        let mut state = TuiState {
+            record_repository,
+            records: vec![],
+            filter: String::new(),
+            list: FilteredList::default(),
+            mode: Mode::Browse,
+            changes: vec![],
+            message: None,
+        };
+        state.reload()?;
+        Ok(state)
+    }
+
+    /// Read records again, keeping selection on the same key if it still exists
+    fn reload(&mut self) -> Result<(), Error> {
+        let selected_key = self.selected_record().map(|record| record.key.to_string());
+        self.records = match ListRecordsService::new(self.record_repository).run(&ListRecordsQuery::new(false))? {
+            ListResult::RecordView(records) => records,
+            ListResult::KeyView(_) => vec![],
+        };
+        self.records.sort_by(|a, b| a.key.cmp(&b.key));
+        self.refilter();
+        let matches = &self.list.matches;
+        if let Some(position) = matches.iter().position(|index| Some(&self.records[*index].key) == selected_key.as_ref()) {
+            self.list.selected = position;
+        }
+        Ok(())
+    }
+
+    fn refilter(&mut self) {
+        let filter = &self.filter;
+        self.list.filter(self.records.iter().map(|record| score(filter, &format!("{} {}", record.key, record.value))));
+    }
+
+    fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        self.list.selected = 0;
+        self.refilter();
+    }
+
+    fn selected_record(&self) -> Option<&Record> {
+        self.list.selected_item().map(|index| &self.records[index])
+    }
+
+    fn delete_selected(&mut self) -> Result<(), Error> {
+        let record = self.selected_record().ok_or_else(|| Error::NotFound("There is no record to delete".to_string()))?.clone();
+        ClearRecordsService::new(self.record_repository).run(&DeleteRecordQuery::new(&Some(record.key.to_string()), false))?;
+        self.message = Some(format!("Deleted \"{}\", press u to undo", record.key));
+        self.changes.push(Change::Deleted(record));
+        self.reload()
+    }
+
+    fn update_selected(&mut self, value: String) -> Result<(), Error> {
+        let record = self.selected_record().ok_or_else(|| Error::NotFound("There is no record to edit".to_string()))?.clone();
+        if record.value == value {
+            self.message = Some("Value was not changed".to_string());
+            return Ok(());
+        }
+        let query = UpdateRecordQuery::new(record.key.to_string(), value);
+        UpdateRecordService::new(self.record_repository).run(&query)?;
+        self.message = Some(format!("Updated \"{}\", press u to undo", record.key));
+        self.changes.push(Change::Edited(record));
+        self.reload()
+    }
+
+    fn undo(&mut self) -> Result<(), Error> {
+        let key = match self.changes.pop() {
+            Some(Change::Deleted(record)) => {
+                let query = AddNewRecordQuery::new(record.key.to_string(), record.value.to_string())
+                    .with_metadata(record.metadata);
+                AddNewRecordService::new(self.record_repository).run(&query)?;
+                record.key
+            }
+            Some(Change::Edited(record)) => {
+                let query = UpdateRecordQuery::new(record.key.to_string(), record.value);
+                UpdateRecordService::new(self.record_repository).run(&query)?;
+                record.key
+            }
+            None => return Err(Error::NotFound("There is nothing to undo".to_string())),
+        };
+        self.message = Some(format!("Restored \"{}\"", key));
+        self.reload()?;
+        if let Some(position) = self.list.matches.iter().position(|index| self.records[*index].key == key) {
+            self.list.selected = position;
+        }
+        Ok(())
+    }
+}
+
+fn describe(entry: &HistoryEntry) -> String {
+    let status = match entry.exit_code {
+        _ if entry.timed_out => "timed out".to_string(),
+        Some(code) => format!("exited with code {}", code),
+        None => "was killed".to_string(),
+    };
+    format!("\"{}\" {} in {:.2}s", entry.key, status, entry.duration_ms as f64 / 1000.0)
+}
+
+/// Run record in the foreground the same way `dumpb exec` does
+fn execute(
+    record: &Record,
+    record_repository: &dyn RecordRepository,
+    history_repository: &dyn HistoryRepository,
+    runner: &dyn CommandRunner,
+) -> Result<String, Error> {
+    let cwd = env::current_dir().map_err(|e| Error::Storage(format!("Could not get current directory: {}", e)))?;
+    let key = record.key.to_string();
+    if record.metadata.is_workflow() {
+        let query = RunWorkflowQuery::new(key, cwd, false, ExecutionLimits::default());
+        let results = RunWorkflowService::new(record_repository, history_repository, runner).run(&query)?;
+        let failed = results.iter().filter(|result| !result.succeeded()).count();
+        return Ok(format!("Workflow \"{}\" finished, {} of {} steps failed", record.key, failed, results.len()));
+    }
+    let query = ExecuteRecordQuery::new(key, cwd, None);
+    ExecuteRecordService::new(record_repository, history_repository, runner).run(&query).map(|entry| describe(&entry))
+}
+
+fn draw(screen: &mut FullScreen, state: &mut TuiState, details: &dyn Fn(&Record) -> String) -> std::io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let (width, height) = (width as usize, height.max(4));
+    let list_width = (width * 2 / 5).max(10).min(width);
+    let list_height = height as usize - 3;
+    state.list.scroll(list_height);
+    let fit = |text: &str, width: usize| -> String { text.replace('\t', "    ").chars().take(width).collect() };
+
+    let mut title = format!(" dumpb  {} of {} records", state.list.matches.len(), state.records.len());
+    if !state.filter.is_empty() || matches!(state.mode, Mode::Filter(_)) {
+        title.push_str("  filter: ");
+    }
+    // Cursor of the filter is put right after this part of the title
+    let filter_x = title.chars().count();
+    title.push_str(&state.filter);
+    let title = format!("{:<width$}", fit(&title, width), width = width);
+    queue!(screen, Clear(ClearType::All), MoveTo(0, 0), SetAttribute(Attribute::Reverse), Print(title), SetAttribute(Attribute::Reset))?;
+
+    let visible = state.list.matches.iter().enumerate().skip(state.list.offset).take(list_height);
+    for (row, (position, index)) in visible.enumerate() {
+        let label = fit(&format!(" {}", state.records[*index].key), list_width.saturating_sub(1));
+        queue!(screen, MoveTo(0, 1 + row as u16))?;
+        if position == state.list.selected {
+            let label = format!("{:<width$}", label, width = list_width.saturating_sub(1));
+            queue!(screen, SetAttribute(Attribute::Reverse), Print(label), SetAttribute(Attribute::Reset))?;
+        } else {
+            queue!(screen, Print(label))?;
+        }
+    }
+    let details_width = width.saturating_sub(list_width + 1);
+    for row in 0..list_height {
+        queue!(screen, MoveTo(list_width as u16, 1 + row as u16), Print("│"))?;
+    }
+    if let Some(record) = state.selected_record() {
+        for (row, line) in details(record).lines().take(list_height).enumerate() {
+            queue!(screen, MoveTo(list_width as u16 + 2, 1 + row as u16), Print(fit(line, details_width.saturating_sub(1))))?;
+        }
+    }
+
+    let status = state.message.as_deref().unwrap_or(HELP);
+    queue!(screen, MoveTo(0, height - 1), SetAttribute(Attribute::Dim), Print(fit(status, width)), SetAttribute(Attribute::Reset))?;
+    match &state.mode {
+        Mode::Edit(input) => {
+            let prompt = "value: ";
+            let room = width.saturating_sub(prompt.len() + 1).max(1);
+            let start = input.cursor.saturating_sub(room);
+            let shown: String = input.text.iter().skip(start).take(room).collect();
+            let cursor_x = (prompt.len() + input.cursor - start) as u16;
+            queue!(screen, MoveTo(0, height - 2), Print(prompt), Print(shown), MoveTo(cursor_x, height - 2), Show)?;
+        }
+        Mode::Filter(input) => queue!(screen, MoveTo((filter_x + input.cursor).min(width - 1) as u16, 0), Show)?,
+        Mode::Browse => queue!(screen, Hide)?,
+    }
+    screen.flush()
+}
+
+/// Ask outside of the full screen if the action has to be confirmed, the screen is left untouched otherwise
+fn confirmed_outside(screen: &mut FullScreen, config: &Config, action: &str, question: &str) -> Result<bool, Error> {
+    if !config.confirm(action)? {
+        return Ok(true);
+    }
+    screen.suspend()?;
+    let answer = confirmed(config, action, question);
+    screen.resume()?;
+    answer
+}
+
+/// Full-screen browser of records, changes are made with the same services as commands use
+/// and need the same confirmations
+pub fn run_tui(
+    record_repository: &dyn RecordRepository,
+    history_repository: &dyn HistoryRepository,
+    runner: &dyn CommandRunner,
+    details: &dyn Fn(&Record) -> String,
+    config: &Config,
+) -> Result<(), Error> {
+    let editor = config.get("editor.command")?;
+    let editor = editor.as_deref();
+    let mut state = TuiState::new(record_repository)?;
+    let mut screen = FullScreen::enter()?;
+    loop {
+        draw(&mut screen, &mut state, details).map_err(|e| Error::Exec(e.to_string()))?;
+        let key = match event::read().map_err(|e| Error::Exec(e.to_string()))? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        if control && key.code == KeyCode::Char('c') {
+            return Ok(());
+        }
+        state.message = None;
+        let page = terminal::size().map_or(1, |(_, height)| height.saturating_sub(3).max(1)) as isize;
+        let result = match (&mut state.mode, key.code) {
+            (Mode::Edit(_), KeyCode::Esc) => {
+                state.mode = Mode::Browse;
+                screen.resume()
+            }
+            (Mode::Edit(input), KeyCode::Enter) => {
+                let value = input.text();
+                state.mode = Mode::Browse;
+                screen.resume().and_then(|_| state.update_selected(value))
+            }
+            (Mode::Edit(input), _) => {
+                input.handle(&key);
+                Ok(())
+            }
+            (Mode::Filter(_), KeyCode::Esc) => {
+                state.set_filter("");
+                state.mode = Mode::Browse;
+                Ok(())
+            }
+            (Mode::Filter(_), KeyCode::Enter) => {
+                state.mode = Mode::Browse;
+                Ok(())
+            }
+            (Mode::Filter(_), KeyCode::Up) => {
+                state.list.move_selection(-1);
+                Ok(())
+            }
+            (Mode::Filter(_), KeyCode::Down) => {
+                state.list.move_selection(1);
+                Ok(())
+            }
+            (Mode::Filter(input), _) => {
+                let before = input.text();
+                if input.handle(&key) && input.text() != before {
+                    let filter = input.text();
+                    state.set_filter(&filter);
+                }
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Char('q')) => return Ok(()),
+            (Mode::Browse, KeyCode::Esc) if state.filter.is_empty() => return Ok(()),
+            (Mode::Browse, KeyCode::Esc) => {
+                state.set_filter("");
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Up) | (Mode::Browse, KeyCode::Char('k')) => {
+                state.list.move_selection(-1);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Down) | (Mode::Browse, KeyCode::Char('j')) => {
+                state.list.move_selection(1);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::PageUp) => {
+                state.list.move_selection(-page);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::PageDown) => {
+                state.list.move_selection(page);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Home) | (Mode::Browse, KeyCode::Char('g')) => {
+                state.list.move_selection(isize::MIN / 2);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::End) | (Mode::Browse, KeyCode::Char('G')) => {
+                state.list.move_selection(isize::MAX / 2);
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Char('/')) => {
+                state.mode = Mode::Filter(LineInput::new(&state.filter));
+                Ok(())
+            }
+            (Mode::Browse, KeyCode::Char('d')) => match state.selected_record().map(|record| record.key.to_string()) {
+                Some(key) => match confirmed_outside(&mut screen, config, "remove", &format!("Remove record \"{}\"?", key)) {
+                    Ok(true) => state.delete_selected(),
+                    Ok(false) => {
+                        state.message = Some("Cancelled".to_string());
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+                None => state.delete_selected(),
+            },
+            (Mode::Browse, KeyCode::Char('u')) => state.undo(),
+            (Mode::Browse, KeyCode::Char('e')) | (Mode::Browse, KeyCode::Char('E')) => match state.selected_record() {
+                // Multi-line values do not fit into a line, so they are edited in the editor
+                Some(record) if key.code == KeyCode::Char('e') && !record.value.contains('\n') => {
+                    state.mode = Mode::Edit(LineInput::new(&record.value));
+                    Ok(())
+                }
+                Some(record) => {
+                    let value = record.value.to_string();
+                    screen
+                        .suspend()
+                        .and_then(|_| edit_text(&value, editor))
+                        .and_then(|edited| screen.resume().and_then(|_| state.update_selected(edited)))
+                }
+                None => Err(Error::NotFound("There is no record to edit".to_string())),
+            },
+            (Mode::Browse, KeyCode::Char('x')) | (Mode::Browse, KeyCode::Enter) => match state.selected_record() {
+                Some(record) => {
+                    let record = record.clone();
+                    if !confirmed_outside(&mut screen, config, "exec", &format!("Execute \"{}\"?", record.key))? {
+                        state.message = Some("Cancelled".to_string());
+                        continue;
+                    }
+                    screen.suspend()?;
+                    println!("$ {}", record.value);
+                    let outcome = execute(&record, record_repository, history_repository, runner);
+                    match &outcome {
+                        Ok(message) => println!("\n{}", message),
+                        Err(e) => println!("\n{}", e),
+                    }
+                    print!("Press any key to return");
+                    std::io::stdout().flush().ok();
+                    terminal::enable_raw_mode().map_err(|e| Error::Exec(e.to_string()))?;
+                    loop {
+                        if let Event::Key(_) = event::read().map_err(|e| Error::Exec(e.to_string()))? {
+                            break;
+                        }
+                    }
+                    screen.resume()?;
+                    outcome.and_then(|message| {
+                        state.message = Some(message);
+                        state.reload()
+                    })
+                }
+                None => Err(Error::NotFound("There is no record to run".to_string())),
+            },
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            state.message = Some(format!("Error: {}", e));
+            screen.resume().ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::record::domain::entities::RecordMetadata;
+    use crate::record::infrastructure::repositories::InMemoryRecordRepository;
+
+    use super::*;
+
+    fn repository() -> InMemoryRecordRepository {
+        let record_repository = InMemoryRecordRepository::new();
+        let metadata = RecordMetadata { tags: vec!["dev".to_string()], ..RecordMetadata::default() };
+        record_repository.add(Record::with_metadata("up", "docker compose up", metadata)).unwrap();
+        record_repository.add(Record::new(&"log".to_string(), &"git log".to_string())).unwrap();
+        record_repository.add(Record::new(&"ps".to_string(), &"docker ps".to_string())).unwrap();
+        record_repository
+    }
+
+    fn visible_keys(state: &TuiState) -> Vec<String> {
+        state.list.matches.iter().map(|index| state.records[*index].key.to_string()).collect()
+    }
+
+    #[test]
+    fn records_are_filtered_by_key_and_value() {
+        let record_repository = repository();
+        let mut state = TuiState::new(&record_repository).unwrap();
+        assert_eq!(visible_keys(&state), vec!["log", "ps", "up"]);
+        state.set_filter("docker");
+        assert_eq!(visible_keys(&state), vec!["ps", "up"]);
+        state.list.move_selection(5);
+        assert_eq!(state.selected_record().unwrap().key, "up");
+    }
+
+    #[test]
+    fn deleted_and_edited_records_are_restored_by_undo() {
+        let record_repository = repository();
+        let mut state = TuiState::new(&record_repository).unwrap();
+        state.list.move_selection(2);
+        state.delete_selected().unwrap();
+        assert!(record_repository.get("up".to_string()).is_err());
+        assert_eq!(state.selected_record().unwrap().key, "ps");
+
+        state.update_selected("docker ps -a".to_string()).unwrap();
+        assert_eq!(record_repository.get("ps".to_string()).unwrap().value, "docker ps -a");
+
+        state.undo().unwrap();
+        assert_eq!(record_repository.get("ps".to_string()).unwrap().value, "docker ps");
+        state.undo().unwrap();
+        let restored = record_repository.get("up".to_string()).unwrap();
+        assert_eq!(restored.metadata.tags, vec!["dev".to_string()]);
+        assert_eq!(state.selected_record().unwrap().key, "up");
+        assert!(state.undo().is_err());
+    }
+
+    #[test]
+    fn line_input_edits_text_at_cursor() {
+        let mut input = LineInput::new("git lg");
+        let press = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        input.handle(&press(KeyCode::Left));
+        input.handle(&press(KeyCode::Char('o')));
+        input.handle(&press(KeyCode::Home));
+        input.handle(&press(KeyCode::Delete));
+        assert_eq!(input.text(), "it log");
+        assert!(!input.handle(&press(KeyCode::Enter)));
+    }
+}
@@ -1 +1,2 @@
-pub mod infrastructure;
\ No newline at end of file
+pub mod domain;
+pub mod infrastructure;